@@ -0,0 +1,89 @@
+//! `cargo bench --bench fuzzy`
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use vonal_search::{
+    best::Best,
+    fuzzy::{Folded, Matcher},
+};
+
+const NUMBER_OF_ENTRIES: usize = 10_000;
+const NUMBER_OF_RESULTS: usize = 7;
+
+const WORDS: [&str; 24] = [
+    "Firefox",
+    "Chromium",
+    "Web",
+    "Browser",
+    "Text",
+    "Editor",
+    "Terminal",
+    "Files",
+    "Image",
+    "Viewer",
+    "Music",
+    "Player",
+    "Office",
+    "Writer",
+    "Calc",
+    "Settings",
+    "System",
+    "Monitor",
+    "Über",
+    "Éditeur",
+    "Текстовый",
+    "редактор",
+    "文本",
+    "编辑器",
+];
+
+/// Names of a few words, like `Text Editor 42`
+fn get_entries() -> Vec<String> {
+    (0..NUMBER_OF_ENTRIES)
+        .map(|i| {
+            let word = |n: usize| WORDS[(i / n + n) % WORDS.len()];
+            format!("{} {} {}{i}", word(1), word(7), word(13).to_lowercase())
+        })
+        .collect()
+}
+
+fn find(texts: &[Folded], query: &str, tolerates_typos: bool) -> Vec<usize> {
+    let mut matcher = Matcher::new(query, true);
+    matcher.set_typos(tolerates_typos);
+    let mut best = Best::new(NUMBER_OF_RESULTS);
+    for (i, text) in texts.iter().enumerate() {
+        if let Some(fitness) = matcher.get_fitness(text) {
+            best.push(fitness, i);
+        }
+    }
+    best.into_sorted_vec()
+}
+
+fn bench_fuzzy(c: &mut Criterion) {
+    let entries = get_entries();
+    c.bench_function("fold 10k", |b| {
+        b.iter(|| {
+            entries
+                .iter()
+                .map(|entry| Folded::new(black_box(entry), true))
+                .collect::<Vec<_>>()
+        })
+    });
+
+    let texts: Vec<Folded> = entries
+        .iter()
+        .map(|entry| Folded::new(entry, true))
+        .collect();
+    for query in ["f", "fox", "txted", "clomium browser", "редак", "编辑"] {
+        c.bench_function(&format!("find {query:?} in 10k"), |b| {
+            b.iter(|| find(&texts, black_box(query), false))
+        });
+    }
+    for query in ["chormium", "txet editro"] {
+        c.bench_function(&format!("find {query:?} with typos in 10k"), |b| {
+            b.iter(|| find(&texts, black_box(query), true))
+        });
+    }
+}
+
+criterion_group!(benches, bench_fuzzy);
+criterion_main!(benches);
@@ -5,10 +5,13 @@ use egui::{
     vec2, Color32, FontId, FontSelection, Id, Image, Margin, RichText, Rounding, Stroke, TextEdit,
 };
 use egui_extras::RetainedImage;
-use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    monitor::MonitorHandle,
+};
 
 use crate::{
-    config::{ConfigBuilder, ConfigError, Dimension},
+    config::{ConfigBuilder, ConfigError, Dimension, ProfileContext, ALLOW_COMMANDS_KEY},
     plugins::{PluginContext, PluginManager},
     theme::list::ListState,
     GlutinWindowContext,
@@ -40,6 +43,8 @@ pub struct App {
     prompt_icon: RetainedImage,
     plugin_manager: PluginManager,
     error: Option<String>,
+    /// names of the config profiles applied on the last configuration
+    active_profiles: Vec<String>,
 }
 
 impl App {
@@ -55,6 +60,7 @@ impl App {
             plugin_manager: PluginManager::new(),
             error: None,
             reset_search_input_cursor: false,
+            active_profiles: Vec::new(),
         }
     }
 }
@@ -118,6 +124,9 @@ impl App {
             gl_window,
             ctx,
         ));
+        if preparation.error.is_some() {
+            self.error = preparation.error;
+        }
 
         // render window
         egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
@@ -188,50 +197,104 @@ impl App {
         self.error = error;
     }
 
-    pub fn configure(&mut self, mut builder: ConfigBuilder) -> Result<ConfigBuilder, ConfigError> {
-        self.config.show_mode_indicator = builder.get_or_create("show_mode_indicator", true)?;
-        self.config.placeholder =
-            builder.get_or_create("placeholder", "Search something ...".to_string())?;
+    pub fn configure(&mut self, builder: ConfigBuilder) -> Result<ConfigBuilder, ConfigError> {
+        let (config, builder) = Self::read_config(builder, &mut self.plugin_manager)?;
+        self.config = config;
+        Ok(builder)
+    }
+
+    /// Reads the config of the app, and configures the plugins of the manager
+    fn read_config(
+        mut builder: ConfigBuilder,
+        plugin_manager: &mut PluginManager,
+    ) -> Result<(AppConfig, ConfigBuilder), ConfigError> {
+        // the builder reads it on its own, this is just for having the entry in the file
+        builder.get_or_create(ALLOW_COMMANDS_KEY, false)?;
+
+        // the fields are only overwritten when the whole config is valid
+        let mut config = AppConfig {
+            show_mode_indicator: builder.get_or_create("show_mode_indicator", true)?,
+            placeholder: builder
+                .get_or_create("placeholder", "Search something ...".to_string())?,
+            ..Default::default()
+        };
 
         builder.group("window", |builder| {
-            self.config.scale_factor = builder.get_or_create("scale_factor", 1.0)?;
+            config.scale_factor = builder.get_or_create("scale_factor", 1.0)?;
 
             builder.group("geometry", |builder| {
-                self.config.window_width =
-                    builder.get_or_create("width", Dimension::Percentage(1.0))?;
-                self.config.window_height =
-                    builder.get_or_create("height", Dimension::Point(300.0))?;
-                self.config.auto_set_window_height =
-                    builder.get_or_create("auto_set_height", true)?;
-                self.config.center_window_horizontally =
+                config.window_width = builder.get_or_create("width", Dimension::Percentage(1.0))?;
+                config.window_height = builder.get_or_create("height", Dimension::Point(300.0))?;
+                config.auto_set_window_height = builder.get_or_create("auto_set_height", true)?;
+                config.center_window_horizontally =
                     builder.get_or_create("center_horizontally", false)?;
-                self.config.center_window_vertically =
+                config.center_window_vertically =
                     builder.get_or_create("center_vertically", false)?;
-                self.config.margin = builder.get_or_create("margin", 0.)?;
+                config.margin = builder.get_or_create("margin", 0.)?;
 
                 Ok(())
             })?;
 
             builder.group("decoration", |builder| {
-                self.config.background =
+                config.background =
                     builder.get_or_create("background", Color32::from_rgb(6, 9, 12))?;
 
                 // border
-                self.config.border_color =
+                config.border_color =
                     builder.get_or_create("border_color", Color32::from_gray(60))?;
-                self.config.border_width = builder.get_or_create("border_width", 1.)?;
-                self.config.border_radius = builder.get_or_create("border_radius", 10.)?;
+                config.border_width = builder.get_or_create("border_width", 1.)?;
+                config.border_radius = builder.get_or_create("border_radius", 10.)?;
 
                 //shadow
-                self.config.shadow_size = builder.get_or_create("shadow_size", 13.)?;
-                self.config.shadow_color =
+                config.shadow_size = builder.get_or_create("shadow_size", 13.)?;
+                config.shadow_color =
                     builder.get_or_create("shadow_color", Color32::from_black_alpha(100))?;
                 Ok(())
             })?;
             Ok(())
         })?;
 
-        self.plugin_manager.configure(builder)
+        let builder = plugin_manager.configure(builder)?;
+        Ok((config, builder))
+    }
+
+    /// Configures fresh plugins, so the running app is either configured fully, or it's left untouched.
+    /// Then the fresh plugins whose config has changed take the place of the running ones.
+    pub fn apply_config(&mut self, builder: ConfigBuilder) -> Result<ConfigBuilder, ConfigError> {
+        let mut plugin_manager = PluginManager::new();
+        let (config, builder) = Self::read_config(builder, &mut plugin_manager)?;
+        self.config = config;
+        self.plugin_manager.replace(plugin_manager, &builder);
+        Ok(builder)
+    }
+
+    /// Configures the app with the profiles matching the machine and the monitor applied.
+    pub fn configure_with_profiles(
+        &mut self,
+        builder: ConfigBuilder,
+        monitor: Option<&MonitorHandle>,
+    ) -> Result<ConfigBuilder, ConfigError> {
+        let profiles = builder.get_matching_profiles(&Self::get_profile_context(monitor));
+        let builder = self.apply_config(builder.with_profiles(&profiles))?;
+        self.active_profiles = profiles;
+        Ok(builder)
+    }
+
+    /// Reconfigures the app if other profiles match than before, e.g. when it's shown on another monitor.
+    pub fn update_profiles(&mut self, monitor: Option<&MonitorHandle>) -> Result<(), ConfigError> {
+        let builder = ConfigBuilder::new()?;
+        let profiles = builder.get_matching_profiles(&Self::get_profile_context(monitor));
+        if profiles != self.active_profiles {
+            self.configure_with_profiles(builder, monitor)?;
+        }
+        Ok(())
+    }
+
+    fn get_profile_context(monitor: Option<&MonitorHandle>) -> ProfileContext {
+        ProfileContext::new(
+            monitor.and_then(MonitorHandle::name),
+            monitor.map(|monitor| (monitor.size().width, monitor.size().height)),
+        )
     }
 
     fn render_search_screen(
@@ -251,6 +314,7 @@ impl App {
             .search(ui, &mut PluginContext::new(&mut self.query, gl_window, ctx));
 
         self.error = post_operation.error;
+        self.reset_search_input_cursor |= post_operation.reset_search_input_cursor;
     }
 
     fn render_error_screen(&self, ui: &mut egui::Ui, error: &String) {
@@ -284,8 +348,9 @@ impl App {
 
         if self.reset_search_input_cursor {
             // Create a new selection range
-            let min = CCursor::new(self.query.len());
-            let max = CCursor::new(self.query.len());
+            // the cursor counts chars, not bytes
+            let min = CCursor::new(self.query.chars().count());
+            let max = CCursor::new(self.query.chars().count());
             let new_range = CCursorRange::two(min, max);
             state.set_ccursor_range(Some(new_range));
             state.store(ui.ctx(), response.id);
@@ -315,6 +380,10 @@ impl App {
 
     fn handle_escape(&mut self, ctx: &egui::Context, window: &GlutinWindowContext) {
         if ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            // the first press only dismisses the error
+            if self.error.take().is_some() {
+                return;
+            }
             if self.query.is_empty() {
                 window.window().set_visible(false);
             }
@@ -0,0 +1,106 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    app::App,
+    config::{get_config_file_path, ConfigBuilder, ConfigError},
+};
+
+const USAGE: &str = "\
+Usage:
+  vonal                              start the daemon
+  vonal config check [path]          validate the config file and print every error
+  vonal config dump-defaults         print the default config with comments
+  vonal config schema                print a JSON schema of the config for editors";
+
+/// Runs the command given in the arguments without starting the daemon, returns the exit code.
+pub fn run(args: &[String]) -> i32 {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["config", "check"] => check(&get_config_file_path()),
+        ["config", "check", path] => check(&PathBuf::from(path)),
+        ["config", "dump-defaults"] => dump_defaults(),
+        ["config", "schema"] => schema(),
+        ["help" | "-h" | "--help"] => {
+            println!("{USAGE}");
+            0
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            2
+        }
+    }
+}
+
+fn check(path: &Path) -> i32 {
+    if !path.exists() {
+        eprintln!("{} does not exist.", path.display());
+        return 1;
+    }
+
+    let builder = match ConfigBuilder::from_file(path) {
+        Ok(builder) => builder,
+        Err(error) => {
+            eprintln!("{error}");
+            return 1;
+        }
+    };
+
+    let profile_names = builder.get_profile_names();
+    let mut errors = get_errors(builder);
+
+    // every profile is checked on its own, only the errors it introduces are reported
+    for name in profile_names {
+        let Ok(builder) = ConfigBuilder::from_file(path) else {
+            continue;
+        };
+        let base_errors: Vec<String> = errors.iter().map(format_error).collect();
+        let profile_errors: Vec<_> = get_errors(builder.with_profiles(std::slice::from_ref(&name)))
+            .into_iter()
+            .filter(|error| !base_errors.contains(&format_error(error)))
+            .map(|(path, error)| (format!("profile.{name}: {path}"), error))
+            .collect();
+        errors.extend(profile_errors);
+    }
+
+    for error in &errors {
+        eprintln!("{}", format_error(error));
+    }
+
+    if errors.is_empty() {
+        println!("{} is valid.", path.display());
+        0
+    } else {
+        1
+    }
+}
+
+fn get_errors(builder: ConfigBuilder) -> Vec<(String, ConfigError)> {
+    match App::new().configure(builder.collecting_errors()) {
+        Ok(mut builder) => builder.take_errors(),
+        Err(error) => vec![(String::new(), error)],
+    }
+}
+
+fn format_error((path, error): &(String, ConfigError)) -> String {
+    if path.is_empty() {
+        error.to_string()
+    } else {
+        format!("{path}: {error}")
+    }
+}
+
+fn get_defaults() -> ConfigBuilder {
+    App::new()
+        .configure(ConfigBuilder::default())
+        .expect("the default config should be valid")
+}
+
+fn dump_defaults() -> i32 {
+    print!("{}", get_defaults().to_commented_toml());
+    0
+}
+
+fn schema() -> i32 {
+    println!("{:#}", get_defaults().to_json_schema());
+    0
+}
@@ -0,0 +1,133 @@
+use std::fmt::Write;
+
+use serde_json::json;
+use toml::{Table, Value};
+
+use super::ConfigBuilder;
+
+impl ConfigBuilder {
+    /// Renders the config as TOML, with the description of every known entry above it.
+    pub fn to_commented_toml(&self) -> String {
+        let mut output = String::new();
+        self.write_table(&mut output, &self.config, None);
+        output
+    }
+
+    /// Builds a JSON schema from the visited entries, so editors can offer completion.
+    pub fn to_json_schema(&self) -> serde_json::Value {
+        let mut schema = self.table_schema(&self.config, None);
+        schema["$schema"] = json!("http://json-schema.org/draft-07/schema#");
+        schema["title"] = json!("Vonal configuration");
+        schema
+    }
+
+    fn write_table(&self, output: &mut String, table: &Table, path: Option<(&str, &str)>) {
+        let (tables, values): (Vec<_>, Vec<_>) =
+            table.iter().partition(|(_, value)| value.is_table());
+
+        if let Some((_, header)) = path {
+            writeln!(output, "\n[{header}]").unwrap();
+        }
+
+        for (key, value) in values {
+            let key_path = join_path(path.map(|(path, _)| path), key);
+            if let Some(description) = self.get_description(&key_path) {
+                writeln!(output, "# {description}").unwrap();
+            }
+            writeln!(output, "{} = {value}", format_key(key)).unwrap();
+        }
+
+        for (key, value) in tables {
+            let key_path = join_path(path.map(|(path, _)| path), key);
+            let header = match path {
+                Some((_, header)) => format!("{header}.{}", format_key(key)),
+                None => format_key(key),
+            };
+            if let Value::Table(table) = value {
+                self.write_table(output, table, Some((&key_path, &header)));
+            }
+        }
+    }
+
+    fn table_schema(&self, table: &Table, path: Option<&str>) -> serde_json::Value {
+        let properties: serde_json::Map<String, serde_json::Value> = table
+            .iter()
+            .map(|(key, value)| {
+                let key_path = join_path(path, key);
+                let mut schema = match value {
+                    Value::Table(table) => self.table_schema(table, Some(&key_path)),
+                    value => {
+                        let mut schema = self
+                            .docs
+                            .get(&key_path)
+                            .and_then(|docs| docs.schema.clone())
+                            .unwrap_or_else(|| json!({}));
+                        schema["default"] = json!(value);
+                        schema
+                    }
+                };
+                if let Some(description) = self.get_description(&key_path) {
+                    schema["description"] = json!(description);
+                }
+                (key.clone(), schema)
+            })
+            .collect();
+
+        json!({ "type": "object", "properties": properties })
+    }
+
+    fn get_description(&self, path: &str) -> Option<&str> {
+        self.docs.get(path)?.description.as_deref()
+    }
+}
+
+fn join_path(path: Option<&str>, key: &str) -> String {
+    match path {
+        Some(path) => format!("{path}.{key}"),
+        None => key.to_string(),
+    }
+}
+
+/// Quotes the key if it cannot be a bare key
+fn format_key(key: &str) -> String {
+    let is_bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if is_bare {
+        key.to_string()
+    } else {
+        Value::String(key.to_string()).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export() {
+        let mut builder = ConfigBuilder::default();
+        builder.get_or_create("enabled", true).unwrap();
+        builder
+            .group("window", |builder| {
+                builder.get_or_create("width", 300)?;
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(
+            builder.to_commented_toml(),
+            "# true or false\nenabled = true\n\n[window]\n\
+             # an integer from -2147483648 to 2147483647\nwidth = 300\n"
+        );
+
+        let schema = builder.to_json_schema();
+        assert_eq!(schema["properties"]["enabled"]["type"], "boolean");
+        assert_eq!(schema["properties"]["window"]["type"], "object");
+        assert_eq!(
+            schema["properties"]["window"]["properties"]["width"]["default"],
+            300
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use std::{env, fmt::Display, process::Command};
+
+use toml::Value;
+
+/// Replaces `${VAR}` and `${VAR:-default}` by environment variables,
+/// and if it's allowed, `$(command)` by the output of the command.
+///
+/// Anything else is kept as it is, so shell syntax like `${VAR-default}` or `$var` still reaches the shell.
+/// `$${` and `$$(` are escapes for a literal `${` and `$(`.
+pub fn interpolate_value(value: &Value, allow_commands: bool) -> Result<Value, InterpolationError> {
+    Ok(match value {
+        Value::String(text) => Value::String(interpolate(text, allow_commands)?),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| interpolate_value(item, allow_commands))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Table(table) => Value::Table(
+            table
+                .iter()
+                .map(|(key, item)| Ok((key.clone(), interpolate_value(item, allow_commands)?)))
+                .collect::<Result<_, _>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+fn interpolate(text: &str, allow_commands: bool) -> Result<String, InterpolationError> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(position) = rest.find('$') {
+        result.push_str(&rest[..position]);
+        rest = &rest[position..];
+
+        if rest.starts_with("$${") || rest.starts_with("$$(") {
+            result.push_str(&rest[1..3]);
+            rest = &rest[3..];
+            continue;
+        }
+
+        let replacement = if rest.starts_with("${") {
+            get_enclosed(&rest[1..], '{', '}')
+                .and_then(|(inner, length)| Some((expand_variable(inner)?, length)))
+        } else if rest.starts_with("$(") && allow_commands {
+            get_enclosed(&rest[1..], '(', ')').map(|(inner, length)| (run_command(inner), length))
+        } else {
+            None
+        };
+
+        match replacement {
+            Some((value, length)) => {
+                result.push_str(&value?);
+                rest = &rest[1 + length..];
+            }
+            None => {
+                result.push('$');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Returns the content between the opening char at the start and its closing pair,
+/// along with the length of the whole enclosed section.
+fn get_enclosed(text: &str, open: char, close: char) -> Option<(&str, usize)> {
+    let mut depth = 0;
+    for (position, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some((&text[1..position], position + 1));
+            }
+        }
+    }
+    None
+}
+
+/// Returns `None` if it's not the supported syntax
+fn expand_variable(inner: &str) -> Option<Result<String, InterpolationError>> {
+    let (name, default) = match inner.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (inner, None),
+    };
+
+    let is_valid_name = !name.is_empty()
+        && !name.starts_with(|c: char| c.is_ascii_digit())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_valid_name {
+        return None;
+    }
+
+    Some(match (env::var(name), default) {
+        (Ok(value), _) if !value.is_empty() => Ok(value),
+        (_, Some(default)) => interpolate(default, false),
+        (Ok(value), None) => Ok(value),
+        (Err(_), None) => Err(InterpolationError::MissingVariable(name.to_string())),
+    })
+}
+
+fn run_command(command: &str) -> Result<String, InterpolationError> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .output()
+        .map_err(|error| InterpolationError::CommandFailed {
+            command: command.to_string(),
+            message: error.to_string(),
+        })?;
+
+    if !output.status.success() {
+        return Err(InterpolationError::CommandFailed {
+            command: command.to_string(),
+            message: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .trim_end_matches('\n')
+        .to_string())
+}
+
+#[derive(Debug)]
+pub enum InterpolationError {
+    MissingVariable(String),
+    CommandFailed { command: String, message: String },
+}
+
+impl Display for InterpolationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpolationError::MissingVariable(name) => write!(
+                f,
+                "The environment variable {name} is not set. Use ${{{name}:-default}} to fall back to a default."
+            ),
+            InterpolationError::CommandFailed { command, message } => {
+                write!(f, "The command \"{command}\" failed: {message}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variables() {
+        env::set_var("VONAL_TEST_STORE", "/store");
+        env::remove_var("VONAL_TEST_MISSING");
+
+        assert_eq!(
+            interpolate("${VONAL_TEST_STORE}/a", false).unwrap(),
+            "/store/a"
+        );
+        assert_eq!(
+            interpolate("${VONAL_TEST_MISSING:-~/.password-store}", false).unwrap(),
+            "~/.password-store"
+        );
+        assert_eq!(
+            interpolate("${VONAL_TEST_MISSING:-${VONAL_TEST_STORE}}", false).unwrap(),
+            "/store"
+        );
+        assert!(matches!(
+            interpolate("${VONAL_TEST_MISSING}", false),
+            Err(InterpolationError::MissingVariable(name)) if name == "VONAL_TEST_MISSING"
+        ));
+    }
+
+    #[test]
+    fn test_shell_syntax_is_kept() {
+        let script = r#"prefix=${PASSWORD_STORE_DIR-~/.password-store}
+printf '%s\n' "${password_files[@]#"$prefix"/}" $(date) $$ ${"#;
+        assert_eq!(interpolate(script, false).unwrap(), script);
+        assert_eq!(
+            interpolate("$${HOME} $$(date)", false).unwrap(),
+            "${HOME} $(date)"
+        );
+    }
+
+    #[test]
+    fn test_commands() {
+        assert_eq!(interpolate("a $(echo b) c", true).unwrap(), "a b c");
+        assert_eq!(
+            interpolate("$(echo $(echo nested))", true).unwrap(),
+            "nested"
+        );
+        assert!(matches!(
+            interpolate("$(exit 1)", true),
+            Err(InterpolationError::CommandFailed { .. })
+        ));
+    }
+}
@@ -1,37 +1,90 @@
 use std::{
+    collections::HashMap,
+    fmt::Display,
     fs,
     path::{Path, PathBuf},
 };
 
 use egui::Color32;
+use serde_json::json;
 use toml::{map::Map, Table, Value};
 
+mod css_colors;
+mod export;
+mod interpolation;
+mod profile;
+mod values;
 pub mod watcher;
 
+pub use profile::ProfileContext;
+pub(crate) use values::config_enum;
+
 pub fn get_config_file_path() -> PathBuf {
     let home = PathBuf::from(std::env::var("HOME").unwrap());
     let config_file = Path::new("./.config/vonal/config.toml");
     home.join(config_file)
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ConfigBuilder {
     config: Table,
+    /// documentation of the visited entries by their dotted path
+    docs: HashMap<String, EntryDocs>,
+    /// if present, bad entries don't fail the build, they are collected here with their dotted path
+    errors: Option<Vec<(String, ConfigError)>>,
+    /// whether `$(command)` is replaced in the string entries
+    allow_commands: bool,
 }
 
+/// The root entry that enables running commands from the config file
+pub const ALLOW_COMMANDS_KEY: &str = "allow_command_substitution";
+
 impl ConfigBuilder {
     pub fn new() -> Result<Self, ConfigError> {
-        let file_path = get_config_file_path();
+        Self::from_file(&get_config_file_path())
+    }
+    pub fn from_file(file_path: &Path) -> Result<Self, ConfigError> {
         let file = fs::read_to_string(file_path).ok();
         let table = match file {
-            Some(file) => file.parse::<Table>().map_err(|_| ConfigError::ParseError)?,
+            Some(file) => file
+                .parse::<Table>()
+                .map_err(|error| ConfigError::ParseError {
+                    message: error.to_string(),
+                })?,
             None => Table::default(),
         };
-        Ok(Self { config: table })
+        Ok(Self::from_table(table))
     }
     pub fn new_safe() -> Self {
         let table = Self::new().map(|s| s.config).unwrap_or_default();
-        Self { config: table }
+        Self::from_table(table)
+    }
+    fn from_table(config: Table) -> Self {
+        let allow_commands = config
+            .get(ALLOW_COMMANDS_KEY)
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        Self {
+            config,
+            allow_commands,
+            ..Default::default()
+        }
+    }
+
+    /// Instead of stopping at the first bad entry, use the default value and keep going.
+    /// The bad entries can be read by [`Self::take_errors`].
+    pub fn collecting_errors(mut self) -> Self {
+        self.errors = Some(Vec::new());
+        self
+    }
+
+    pub fn take_errors(&mut self) -> Vec<(String, ConfigError)> {
+        self.errors.take().unwrap_or_default()
+    }
+
+    /// The raw entry, e.g. for comparing a group with its previous state
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.config.get(name)
     }
 
     pub fn save(&self) -> Result<(), std::io::Error> {
@@ -47,11 +100,45 @@ impl ConfigBuilder {
         name: &'static str,
         value: T,
     ) -> Result<T, ConfigError> {
-        let entry = self.config.entry(name).or_insert_with(|| value.to_config());
-        T::from_config(entry).ok_or(ConfigError::BadEntryError {
-            name,
-            message: None,
-        })
+        self.docs.insert(
+            name.to_string(),
+            EntryDocs {
+                description: T::expected(),
+                schema: T::schema(),
+            },
+        );
+
+        let default = value.to_config();
+        // the entry is only interpolated for reading, so it's saved as it was written
+        let entry = self.config.entry(name).or_insert_with(|| default.clone());
+        let error = match interpolation::interpolate_value(entry, self.allow_commands) {
+            Ok(interpolated) => match T::from_config(&interpolated) {
+                Some(value) => return Ok(value),
+                None => ConfigError::BadEntryError {
+                    name,
+                    message: T::expected().map(|expected| {
+                        format!(
+                            "Wrong config file entry at {name}. Expected {expected}, found {entry}."
+                        )
+                    }),
+                },
+            },
+            Err(error) => ConfigError::BadEntryError {
+                name,
+                message: Some(format!("Wrong config file entry at {name}. {error}")),
+            },
+        };
+
+        match self.errors.as_mut() {
+            Some(errors) => {
+                errors.push((name.to_string(), error));
+                T::from_config(&default).ok_or(ConfigError::BadEntryError {
+                    name,
+                    message: None,
+                })
+            }
+            None => Err(error),
+        }
     }
 
     pub fn group(
@@ -66,8 +153,25 @@ impl ConfigBuilder {
             .cloned()
             .unwrap_or_default();
 
-        let mut builder = Self { config };
-        build(&mut builder)?;
+        let mut builder = Self {
+            config,
+            docs: HashMap::new(),
+            errors: self.errors.as_ref().map(|_| Vec::new()),
+            allow_commands: self.allow_commands,
+        };
+        let result = build(&mut builder);
+
+        for (key, docs) in builder.docs {
+            self.docs.insert(format!("{name}.{key}"), docs);
+        }
+        if let (Some(errors), Some(group_errors)) = (self.errors.as_mut(), builder.errors) {
+            errors.extend(
+                group_errors
+                    .into_iter()
+                    .map(|(key, error)| (format!("{name}.{key}"), error)),
+            );
+        }
+        result?;
 
         self.config
             .insert(name.to_string(), Value::Table(builder.config));
@@ -76,20 +180,60 @@ impl ConfigBuilder {
     }
 }
 
-#[derive(Debug)]
+/// What the dumped default config and the JSON schema tell about an entry
+#[derive(Clone)]
+struct EntryDocs {
+    description: Option<String>,
+    schema: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone)]
 pub enum ConfigError {
-    ParseError,
+    ParseError {
+        message: String,
+    },
     BadEntryError {
-        #[allow(dead_code)]
         name: &'static str,
         message: Option<String>,
     },
 }
 
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::ParseError { message } => write!(f, "Config syntax error: {message}"),
+            ConfigError::BadEntryError {
+                message: Some(message),
+                ..
+            } => f.write_str(message),
+            ConfigError::BadEntryError {
+                name,
+                message: None,
+            } => write!(f, "Wrong config file entry at {name}."),
+        }
+    }
+}
+
 pub trait FromConfig {
     fn from_config(raw: &Value) -> Option<Self>
     where
         Self: Sized;
+
+    /// A short description of the accepted values, used in the error message of a bad entry.
+    fn expected() -> Option<String>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// JSON schema of the accepted values, used for editor completion.
+    fn schema() -> Option<serde_json::Value>
+    where
+        Self: Sized,
+    {
+        None
+    }
 }
 
 pub trait ToConfig {
@@ -98,7 +242,17 @@ pub trait ToConfig {
 
 impl FromConfig for Color32 {
     fn from_config(raw: &Value) -> Option<Self> {
-        let value = raw.as_str()?;
+        let value = raw.as_str()?.trim();
+        if let Some(hex) = value.strip_prefix('#') {
+            return parse_hex_color(hex);
+        }
+        if let Some([r, g, b]) = css_colors::get_css_color(value) {
+            return Some(Color32::from_rgb(r, g, b));
+        }
+        if value.eq_ignore_ascii_case("transparent") {
+            return Some(Color32::TRANSPARENT);
+        }
+
         let mut split = value
             .trim_start_matches("rgb(")
             .trim_start_matches("rgba(")
@@ -110,6 +264,41 @@ impl FromConfig for Color32 {
         let a: u8 = split.next().unwrap_or("255").trim().parse().ok()?;
         Some(Color32::from_rgba_unmultiplied(r, g, b, a))
     }
+
+    fn expected() -> Option<String> {
+        Some(r##"a color like "#rrggbb", "#rrggbbaa", "rgb(r, g, b)", "rgba(r, g, b, a)" or a CSS color name"##.into())
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": "string" }))
+    }
+}
+
+/// Parses `rgb`, `rgba`, `rrggbb` and `rrggbbaa` hex notations (without the `#`)
+fn parse_hex_color(hex: &str) -> Option<Color32> {
+    if !hex.is_ascii() {
+        return None;
+    }
+    let channels: Vec<u8> = match hex.len() {
+        3 | 4 => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string(), 16).map(|x| x * 17))
+            .collect::<Result<_, _>>()
+            .ok()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+            .collect::<Result<_, _>>()
+            .ok()?,
+        _ => return None,
+    };
+    let a = channels.get(3).copied().unwrap_or(255);
+    Some(Color32::from_rgba_unmultiplied(
+        channels[0],
+        channels[1],
+        channels[2],
+        a,
+    ))
 }
 
 impl ToConfig for Color32 {
@@ -133,6 +322,20 @@ macro_rules! impl_for_integers {
                 fn from_config(raw: &Value) -> Option<Self> {
                     raw.as_integer().and_then(|x| Self::try_from(x).ok())
                 }
+
+                #[allow(unused_comparisons)]
+                fn expected() -> Option<String> {
+                    let is_huge = Self::MAX as i128 > u32::MAX as i128;
+                    Some(match (is_huge, Self::MIN < 0) {
+                        (true, true) => "an integer".into(),
+                        (true, false) => "a non-negative integer".into(),
+                        (false, _) => format!("an integer from {} to {}", Self::MIN, Self::MAX),
+                    })
+                }
+
+                fn schema() -> Option<serde_json::Value> {
+                    Some(json!({ "type": "integer", "minimum": Self::MIN, "maximum": Self::MAX }))
+                }
             }
             impl ToConfig for $int_type {
                 fn to_config(self) -> Value {
@@ -152,6 +355,14 @@ macro_rules! impl_for_floats {
                 fn from_config(raw: &Value) -> Option<Self> {
                     Some(raw.as_float()? as $int_type)
                 }
+
+                fn expected() -> Option<String> {
+                    Some("a floating point number like 1.0".into())
+                }
+
+                fn schema() -> Option<serde_json::Value> {
+                    Some(json!({ "type": "number" }))
+                }
             }
             impl ToConfig for $int_type {
                 fn to_config(self) -> Value {
@@ -168,6 +379,14 @@ impl FromConfig for bool {
     fn from_config(raw: &Value) -> Option<Self> {
         raw.as_bool()
     }
+
+    fn expected() -> Option<String> {
+        Some("true or false".into())
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": "boolean" }))
+    }
 }
 impl ToConfig for bool {
     fn to_config(self) -> Value {
@@ -179,6 +398,14 @@ impl FromConfig for String {
     fn from_config(raw: &Value) -> Option<Self> {
         Some(raw.as_str()?.to_string())
     }
+
+    fn expected() -> Option<String> {
+        Some("a string".into())
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": "string" }))
+    }
 }
 impl ToConfig for String {
     fn to_config(self) -> Value {
@@ -193,6 +420,14 @@ impl<T: FromConfig> FromConfig for Vec<T> {
             .map(|item| T::from_config(item))
             .collect()
     }
+
+    fn expected() -> Option<String> {
+        T::expected().map(|expected| format!("a list of items, each being {expected}"))
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": "array", "items": T::schema().unwrap_or_else(|| json!({})) }))
+    }
 }
 impl<T: ToConfig> ToConfig for Vec<T> {
     fn to_config(self) -> Value {
@@ -200,10 +435,47 @@ impl<T: ToConfig> ToConfig for Vec<T> {
     }
 }
 
+impl<T: FromConfig> FromConfig for HashMap<String, T> {
+    fn from_config(raw: &Value) -> Option<Self> {
+        raw.as_table()?
+            .iter()
+            .map(|(key, value)| Some((key.clone(), T::from_config(value)?)))
+            .collect()
+    }
+
+    fn expected() -> Option<String> {
+        T::expected().map(|expected| format!("a table of entries, each being {expected}"))
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({
+            "type": "object",
+            "additionalProperties": T::schema().unwrap_or_else(|| json!({})),
+        }))
+    }
+}
+impl<T: ToConfig> ToConfig for HashMap<String, T> {
+    fn to_config(self) -> Value {
+        Value::Table(
+            self.into_iter()
+                .map(|(key, value)| (key, value.to_config()))
+                .collect(),
+        )
+    }
+}
+
 impl FromConfig for Map<String, Value> {
     fn from_config(raw: &Value) -> Option<Self> {
         raw.as_table().cloned()
     }
+
+    fn expected() -> Option<String> {
+        Some("a table".into())
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": "object" }))
+    }
 }
 impl ToConfig for Map<String, Value> {
     fn to_config(self) -> Value {
@@ -257,6 +529,14 @@ impl FromConfig for Dimension {
             Some(Dimension::Point(raw.as_float()?))
         }
     }
+
+    fn expected() -> Option<String> {
+        Some(r#"a number of points like 300.0 or a percentage like "50%""#.into())
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": ["number", "string"] }))
+    }
 }
 
 impl ToConfig for Dimension {
@@ -277,6 +557,14 @@ impl<T: FromConfig> FromConfig for Option<T> {
             Some(Some(T::from_config(raw)?))
         }
     }
+
+    fn expected() -> Option<String> {
+        T::expected().map(|expected| format!(r#""auto" or {expected}"#))
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        T::schema().map(|schema| json!({ "anyOf": [{ "const": "auto" }, schema] }))
+    }
 }
 
 impl<T: ToConfig> ToConfig for Option<T> {
@@ -287,3 +575,30 @@ impl<T: ToConfig> ToConfig for Option<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(value: &str) -> Option<Color32> {
+        Color32::from_config(&Value::String(value.into()))
+    }
+
+    #[test]
+    fn test_color() {
+        assert_eq!(color("rgb(1, 2, 3)"), Some(Color32::from_rgb(1, 2, 3)));
+        assert_eq!(color("#0a0B0c"), Some(Color32::from_rgb(10, 11, 12)));
+        assert_eq!(
+            color("#ff000080"),
+            Some(Color32::from_rgba_unmultiplied(255, 0, 0, 128))
+        );
+        assert_eq!(color("#f00"), Some(Color32::from_rgb(255, 0, 0)));
+        assert_eq!(
+            color("RebeccaPurple"),
+            Some(Color32::from_rgb(102, 51, 153))
+        );
+        assert_eq!(color("#12345"), None);
+        assert_eq!(color("#ééé"), None);
+        assert_eq!(color("notacolor"), None);
+    }
+}
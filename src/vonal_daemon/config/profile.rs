@@ -0,0 +1,199 @@
+use std::{env, fs};
+
+use toml::{Table, Value};
+
+use super::ConfigBuilder;
+
+/// The name of the table containing the profiles
+const PROFILES_KEY: &str = "profile";
+/// The name of the table inside a profile, which contains its conditions
+const CONDITIONS_KEY: &str = "when";
+
+/// Facts about the current machine and the focused monitor, which a profile can match.
+#[derive(Debug, Default)]
+pub struct ProfileContext {
+    pub hostname: Option<String>,
+    pub monitor_name: Option<String>,
+    pub resolution: Option<(u32, u32)>,
+}
+
+impl ProfileContext {
+    pub fn new(monitor_name: Option<String>, resolution: Option<(u32, u32)>) -> Self {
+        Self {
+            hostname: get_hostname(),
+            monitor_name,
+            resolution,
+        }
+    }
+
+    /// Every given condition has to match. Unknown conditions never match.
+    fn matches(&self, conditions: &Table) -> bool {
+        conditions
+            .iter()
+            .all(|(name, expected)| match name.as_str() {
+                "hostname" => matches_any(expected, self.hostname.as_deref()),
+                "monitor" => matches_any(expected, self.monitor_name.as_deref()),
+                "resolution" => {
+                    let resolution = self
+                        .resolution
+                        .map(|(width, height)| format!("{width}x{height}"));
+                    matches_any(expected, resolution.as_deref())
+                }
+                "env" => expected.as_table().is_some_and(|variables| {
+                    variables.iter().all(|(variable, expected)| {
+                        matches_any(expected, env::var(variable).ok().as_deref())
+                    })
+                }),
+                _ => false,
+            })
+    }
+}
+
+/// The expected value is either a string or a list of strings
+fn matches_any(expected: &Value, actual: Option<&str>) -> bool {
+    let Some(actual) = actual else {
+        return false;
+    };
+    match expected {
+        Value::String(expected) => expected == actual,
+        Value::Array(expected) => expected.iter().any(|x| x.as_str() == Some(actual)),
+        _ => false,
+    }
+}
+
+fn get_hostname() -> Option<String> {
+    fs::read_to_string("/proc/sys/kernel/hostname")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .ok()
+        .map(|hostname| hostname.trim().to_string())
+        .or_else(|| env::var("HOSTNAME").ok())
+}
+
+impl ConfigBuilder {
+    /// Returns the names of the profiles matching the context, in alphabetical order.
+    pub fn get_matching_profiles(&self, context: &ProfileContext) -> Vec<String> {
+        self.get_profiles()
+            .map(|profiles| {
+                profiles
+                    .iter()
+                    .filter(|(_, profile)| {
+                        profile
+                            .get(CONDITIONS_KEY)
+                            .and_then(Value::as_table)
+                            .is_some_and(|conditions| context.matches(conditions))
+                    })
+                    .map(|(name, _)| name.clone())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Overrides the base entries by the entries of the given profiles.
+    /// The result is meant to be read only, it should not be saved.
+    pub fn with_profiles(mut self, names: &[String]) -> Self {
+        let overrides: Vec<Table> = names
+            .iter()
+            .filter_map(|name| {
+                let mut profile = self.get_profiles()?.get(name)?.as_table()?.clone();
+                profile.remove(CONDITIONS_KEY);
+                Some(profile)
+            })
+            .collect();
+
+        for profile in overrides {
+            merge(&mut self.config, profile);
+        }
+        self
+    }
+
+    pub fn get_profile_names(&self) -> Vec<String> {
+        self.get_profiles()
+            .map(|profiles| profiles.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn get_profiles(&self) -> Option<&Table> {
+        self.config.get(PROFILES_KEY)?.as_table()
+    }
+}
+
+/// Deep merge, so a profile can override a single entry of a group
+fn merge(base: &mut Table, overrides: Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(overrides)) => merge(base, overrides),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        placeholder = "base"
+
+        [window]
+        scale_factor = 1.0
+        margin = 5.0
+
+        [profile.big.when]
+        resolution = ["3840x2160", "5120x2880"]
+
+        [profile.big.window]
+        scale_factor = 2.0
+
+        [profile.work.when]
+        hostname = "work-laptop"
+        monitor = "eDP-1"
+
+        [profile.work]
+        placeholder = "work"
+    "#;
+
+    fn builder() -> ConfigBuilder {
+        ConfigBuilder {
+            config: CONFIG.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_matching_profiles() {
+        let context = ProfileContext {
+            hostname: Some("work-laptop".into()),
+            monitor_name: Some("eDP-1".into()),
+            resolution: Some((3840, 2160)),
+        };
+        assert_eq!(
+            builder().get_matching_profiles(&context),
+            vec!["big", "work"]
+        );
+
+        let context = ProfileContext {
+            hostname: Some("work-laptop".into()),
+            monitor_name: Some("HDMI-1".into()),
+            resolution: Some((1920, 1080)),
+        };
+        assert!(builder().get_matching_profiles(&context).is_empty());
+    }
+
+    #[test]
+    fn test_with_profiles() {
+        let mut builder = builder().with_profiles(&["big".into()]);
+        assert_eq!(
+            builder.get_or_create("placeholder", String::new()).unwrap(),
+            "base"
+        );
+        builder
+            .group("window", |builder| {
+                assert_eq!(builder.get_or_create("scale_factor", 0.)?, 2.);
+                assert_eq!(builder.get_or_create("margin", 0.)?, 5.);
+                Ok(())
+            })
+            .unwrap();
+    }
+}
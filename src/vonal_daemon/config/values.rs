@@ -0,0 +1,364 @@
+use std::{env, path::PathBuf, time::Duration};
+
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde_json::json;
+use toml::Value;
+
+use super::{FromConfig, ToConfig};
+
+/// Declares a fieldless enum which is stored as one of the given strings in the config file.
+///
+/// ```
+/// config_enum! {
+///     pub enum Position {
+///         Top => "top",
+///         Bottom => "bottom",
+///     }
+/// }
+/// ```
+macro_rules! config_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident => $value:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        $vis enum $name {
+            $($(#[$variant_meta])* $variant),*
+        }
+
+        impl $crate::config::FromConfig for $name {
+            fn from_config(raw: &toml::Value) -> Option<Self> {
+                let value = raw.as_str()?.trim();
+                $(
+                    if value.eq_ignore_ascii_case($value) {
+                        return Some(Self::$variant);
+                    }
+                )*
+                None
+            }
+
+            fn expected() -> Option<String> {
+                let variants: &[&str] = &[$(concat!("\"", $value, "\"")),*];
+                Some(format!("one of {}", variants.join(", ")))
+            }
+
+            fn schema() -> Option<serde_json::Value> {
+                Some(serde_json::json!({ "enum": [$($value),*] }))
+            }
+        }
+
+        impl $crate::config::ToConfig for $name {
+            fn to_config(self) -> toml::Value {
+                let value = match self {
+                    $(Self::$variant => $value),*
+                };
+                toml::Value::String(value.to_string())
+            }
+        }
+    };
+}
+pub(crate) use config_enum;
+
+impl FromConfig for Duration {
+    fn from_config(raw: &Value) -> Option<Self> {
+        parse_duration(raw.as_str()?)
+    }
+
+    fn expected() -> Option<String> {
+        Some(r#"a duration like "300ms", "5s", "2m" or "1h 30m""#.into())
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": "string" }))
+    }
+}
+
+impl ToConfig for Duration {
+    fn to_config(self) -> Value {
+        let millis = self.as_millis();
+        let formatted = if millis == 0 {
+            "0ms".to_string()
+        } else if millis.is_multiple_of(3_600_000) {
+            format!("{}h", millis / 3_600_000)
+        } else if millis.is_multiple_of(60_000) {
+            format!("{}m", millis / 60_000)
+        } else if millis.is_multiple_of(1000) {
+            format!("{}s", millis / 1000)
+        } else {
+            format!("{millis}ms")
+        };
+        Value::String(formatted)
+    }
+}
+
+/// Parses human durations, which are a sequence of numbers with units (`ms`, `s`, `m`, `h`).
+fn parse_duration(value: &str) -> Option<Duration> {
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut seconds = 0.;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = rest[number_end..].trim_start();
+
+        let unit_end = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let multiplier = match &rest[..unit_end] {
+            "ms" => 0.001,
+            "s" | "sec" => 1.,
+            "m" | "min" => 60.,
+            "h" => 3600.,
+            _ => return None,
+        };
+        rest = rest[unit_end..].trim_start();
+
+        seconds += number * multiplier;
+    }
+
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+impl FromConfig for PathBuf {
+    fn from_config(raw: &Value) -> Option<Self> {
+        expand_path(raw.as_str()?)
+    }
+
+    fn expected() -> Option<String> {
+        Some(
+            r#"a path, optionally starting with "~" and containing only defined $VARIABLES"#.into(),
+        )
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": "string" }))
+    }
+}
+
+impl ToConfig for PathBuf {
+    fn to_config(self) -> Value {
+        Value::String(self.to_string_lossy().into())
+    }
+}
+
+/// Expands the leading `~` and every `$VAR` or `${VAR}`.
+/// Returns `None` if a referenced variable is not set.
+fn expand_path(path: &str) -> Option<PathBuf> {
+    let path = expand_env_vars(path)?;
+    if path == "~" {
+        return Some(PathBuf::from(env::var("HOME").ok()?));
+    }
+    if let Some(rest) = path.strip_prefix("~/") {
+        return Some(PathBuf::from(env::var("HOME").ok()?).join(rest));
+    }
+    Some(PathBuf::from(path))
+}
+
+fn expand_env_vars(value: &str) -> Option<String> {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            result.push(c);
+            continue;
+        }
+
+        let mut name = String::new();
+        if chars.next_if_eq(&'{').is_some() {
+            name.extend(chars.by_ref().take_while(|c| *c != '}'));
+        } else {
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                name.push(c);
+            }
+        }
+
+        if name.is_empty() {
+            result.push('$');
+        } else {
+            result.push_str(&env::var(name).ok()?);
+        }
+    }
+
+    Some(result)
+}
+
+impl FromConfig for KeyboardShortcut {
+    fn from_config(raw: &Value) -> Option<Self> {
+        parse_key_chord(raw.as_str()?)
+    }
+
+    fn expected() -> Option<String> {
+        Some(r#"a key chord like "Ctrl+Shift+K", "Alt+Enter" or "F5""#.into())
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        Some(json!({ "type": "string" }))
+    }
+}
+
+impl ToConfig for KeyboardShortcut {
+    fn to_config(self) -> Value {
+        let modifiers = [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.alt, "Alt"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.command && !self.modifiers.ctrl, "Cmd"),
+        ];
+        let mut names: Vec<&str> = modifiers
+            .into_iter()
+            .filter_map(|(is_pressed, name)| is_pressed.then_some(name))
+            .collect();
+        names.push(self.key.name());
+        Value::String(names.join("+"))
+    }
+}
+
+fn parse_key_chord(value: &str) -> Option<KeyboardShortcut> {
+    let tokens: Vec<&str> = value.split('+').map(str::trim).collect();
+    let (key, modifier_names) = tokens.split_last()?;
+
+    let mut modifiers = Modifiers::NONE;
+    for name in modifier_names {
+        modifiers = modifiers
+            | match name.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Modifiers::CTRL,
+                "alt" => Modifiers::ALT,
+                "shift" => Modifiers::SHIFT,
+                "cmd" | "command" => Modifiers::COMMAND,
+                _ => return None,
+            };
+    }
+
+    Some(KeyboardShortcut::new(modifiers, parse_key(key)?))
+}
+
+fn parse_key(name: &str) -> Option<Key> {
+    let name = name.to_ascii_lowercase();
+    let name = match name.as_str() {
+        "esc" => "escape",
+        "return" => "enter",
+        "del" => "delete",
+        "pgup" => "pageup",
+        "pgdn" | "pgdown" => "pagedown",
+        "arrowup" => "up",
+        "arrowdown" => "down",
+        "arrowleft" => "left",
+        "arrowright" => "right",
+        "-" => "minus",
+        "=" | "equals" => "plus",
+        name => name,
+    };
+    ALL_KEYS
+        .iter()
+        .copied()
+        .find(|key| key.name().eq_ignore_ascii_case(name))
+}
+
+#[rustfmt::skip]
+const ALL_KEYS: &[Key] = &[
+    Key::ArrowDown, Key::ArrowLeft, Key::ArrowRight, Key::ArrowUp,
+    Key::Escape, Key::Tab, Key::Backspace, Key::Enter, Key::Space,
+    Key::Insert, Key::Delete, Key::Home, Key::End, Key::PageUp, Key::PageDown,
+    Key::Minus, Key::PlusEquals,
+    Key::Num0, Key::Num1, Key::Num2, Key::Num3, Key::Num4,
+    Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I,
+    Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R,
+    Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10,
+    Key::F11, Key::F12, Key::F13, Key::F14, Key::F15, Key::F16, Key::F17, Key::F18, Key::F19, Key::F20,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    config_enum! {
+        enum Position {
+            Top => "top",
+            Bottom => "bottom",
+        }
+    }
+
+    fn string(value: &str) -> Value {
+        Value::String(value.into())
+    }
+
+    #[test]
+    fn test_duration() {
+        let parse = |value| Duration::from_config(&string(value));
+        assert_eq!(parse("300ms"), Some(Duration::from_millis(300)));
+        assert_eq!(parse("5s"), Some(Duration::from_secs(5)));
+        assert_eq!(parse("1.5s"), Some(Duration::from_millis(1500)));
+        assert_eq!(parse("1h 30m"), Some(Duration::from_secs(5400)));
+        assert_eq!(parse("5"), None);
+        assert_eq!(parse("5 days"), None);
+        assert_eq!(parse(""), None);
+
+        assert_eq!(Duration::from_millis(300).to_config(), string("300ms"));
+        assert_eq!(Duration::from_secs(120).to_config(), string("2m"));
+    }
+
+    #[test]
+    fn test_path() {
+        env::set_var("VONAL_TEST_DIR", "/tmp/vonal");
+        let home = PathBuf::from(env::var("HOME").unwrap());
+
+        assert_eq!(expand_path("~/a"), Some(home.join("a")));
+        assert_eq!(
+            expand_path("$VONAL_TEST_DIR/a"),
+            Some("/tmp/vonal/a".into())
+        );
+        assert_eq!(
+            expand_path("${VONAL_TEST_DIR}a"),
+            Some("/tmp/vonala".into())
+        );
+        assert_eq!(expand_path("/a/~/$"), Some("/a/~/$".into()));
+        assert_eq!(expand_path("$VONAL_TEST_UNDEFINED/a"), None);
+    }
+
+    #[test]
+    fn test_key_chord() {
+        let parse = |value| KeyboardShortcut::from_config(&string(value));
+        assert_eq!(
+            parse("ctrl+shift+k"),
+            Some(KeyboardShortcut::new(
+                Modifiers::CTRL | Modifiers::SHIFT,
+                Key::K
+            ))
+        );
+        assert_eq!(
+            parse("Alt + Return"),
+            Some(KeyboardShortcut::new(Modifiers::ALT, Key::Enter))
+        );
+        assert_eq!(
+            parse("F5"),
+            Some(KeyboardShortcut::new(Modifiers::NONE, Key::F5))
+        );
+        assert_eq!(parse("Hyper+K"), None);
+        assert_eq!(parse("Ctrl+"), None);
+
+        let chord = KeyboardShortcut::new(Modifiers::CTRL | Modifiers::SHIFT, Key::ArrowUp);
+        assert_eq!(chord.to_config(), string("Ctrl+Shift+Up"));
+    }
+
+    #[test]
+    fn test_enum() {
+        assert_eq!(Position::from_config(&string("Top")), Some(Position::Top));
+        assert_eq!(Position::from_config(&string("left")), None);
+        assert_eq!(Position::Bottom.to_config(), string("bottom"));
+        assert_eq!(
+            Position::expected(),
+            Some(r#"one of "top", "bottom""#.to_string())
+        );
+    }
+}
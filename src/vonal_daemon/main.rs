@@ -1,10 +1,10 @@
 use std::error::Error;
 use std::io::{BufRead, BufReader};
-use std::sync::Arc;
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
 use std::{fs, os::unix::net::UnixListener, path::Path, sync::mpsc, time::Instant};
 use std::{os::unix::net::UnixStream, thread};
 
-use crate::config::ConfigError;
 use common::{Command, Commands};
 use config::watcher::ConfigEvent;
 use config::ConfigBuilder;
@@ -13,10 +13,11 @@ use egui_glow::glow::{self};
 use egui_glow::painter::Context;
 use windowing::GlutinWindowContext;
 use winit::event::{Event, StartCause, WindowEvent};
-use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder};
+use winit::event_loop::{ControlFlow, EventLoop, EventLoopBuilder, EventLoopProxy};
 use winit::platform::run_return::EventLoopExtRunReturn;
 
 mod app;
+mod cli;
 #[path = "../common.rs"]
 mod common;
 mod config;
@@ -26,6 +27,11 @@ mod utils;
 mod windowing;
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     let mut app = app::App::new();
 
     let (tx, rx) = mpsc::channel();
@@ -40,7 +46,7 @@ fn main() {
     });
 
     // start listening for config file changes
-    let config_builder = app.configure(ConfigBuilder::new_safe()).unwrap();
+    let config_builder = app.apply_config(ConfigBuilder::new_safe()).unwrap();
     config_builder.save().unwrap();
     thread::spawn(move || {
         if let Err(error) = start_config_watcher(&tx) {
@@ -106,6 +112,16 @@ fn start_gui(mut app: app::App, rx: mpsc::Receiver<UserEvent>) {
 
     let proxy = event_loop.create_proxy();
 
+    // background threads (e.g. the watchers of the launched apps) can ask for a repaint too
+    let repaint_timer = Mutex::new(start_repaint_timer(event_loop.create_proxy()));
+    egui_glow
+        .egui_ctx
+        .set_request_repaint_callback(move |info| {
+            if let Some(instant) = Instant::now().checked_add(info.after) {
+                let _ = repaint_timer.lock().unwrap().send(instant);
+            }
+        });
+
     thread::spawn(move || {
         while let Ok(message) = rx.recv() {
             proxy.send_event(message).expect("Couldn't send message");
@@ -124,6 +140,35 @@ fn start_gui(mut app: app::App, rx: mpsc::Receiver<UserEvent>) {
     });
 }
 
+/// Sends a repaint event at the earliest of the received instants
+fn start_repaint_timer(proxy: EventLoopProxy<UserEvent>) -> mpsc::Sender<Instant> {
+    let (tx, rx) = mpsc::channel::<Instant>();
+    thread::spawn(move || {
+        let mut deadline: Option<Instant> = None;
+        loop {
+            let received = match deadline {
+                Some(deadline) => {
+                    rx.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                }
+                None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+            };
+            match received {
+                Ok(instant) => {
+                    deadline = Some(deadline.map_or(instant, |deadline| deadline.min(instant)))
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    deadline = None;
+                    if proxy.send_event(UserEvent::Repaint).is_err() {
+                        break;
+                    }
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+    });
+    tx
+}
+
 fn handle_platform_event(
     event: Event<UserEvent>,
     control_flow: &mut ControlFlow,
@@ -169,28 +214,21 @@ fn handle_platform_event(
             parse_cli(commands.0, gl_window, app);
         }
         Event::UserEvent(UserEvent::Quit) => control_flow.set_exit(),
+        Event::UserEvent(UserEvent::Repaint) => gl_window.window().request_redraw(),
         Event::UserEvent(UserEvent::ConfigEvent(event)) => match event {
             ConfigEvent::Created => println!("Config file created"),
             ConfigEvent::Deleted => println!("Config file deleted"),
             ConfigEvent::Modified => {
                 println!("Config file modified");
-                let result = ConfigBuilder::new().and_then(|builder| app.configure(builder));
+                let monitor = gl_window.get_focused_monitor();
+                let result = ConfigBuilder::new()
+                    .and_then(|builder| app.configure_with_profiles(builder, monitor.as_ref()));
                 match result {
                     Ok(_) => {
                         println!("Config has reloaded");
                         app.set_error(None);
                     }
-                    Err(ConfigError::BadEntryError {
-                        message: Some(message),
-                        ..
-                    }) => app.set_error(Some(message)),
-                    Err(ConfigError::BadEntryError {
-                        name,
-                        message: None,
-                    }) => app.set_error(Some(format!("Wrong config file entry at {name}."))),
-                    Err(ConfigError::ParseError) => {
-                        app.set_error(Some("Config syntax error".to_string()))
-                    }
+                    Err(error) => app.set_error(Some(error.to_string())),
                 }
                 gl_window.window().request_redraw();
             }
@@ -203,11 +241,11 @@ fn handle_platform_event(
 fn parse_cli(commands: Vec<Command>, gl_window: &GlutinWindowContext, app: &mut app::App) {
     for command in &commands {
         match command {
-            Command::Show => show_window(gl_window, true),
+            Command::Show => show_window(gl_window, app, true),
             Command::Hide => hide_window(gl_window),
             Command::Toggle => {
                 let show = !gl_window.window().is_visible().unwrap_or(false);
-                show_window(gl_window, show);
+                show_window(gl_window, app, show);
             }
             Command::SetQuery { query } => {
                 app.query = query.into();
@@ -230,11 +268,16 @@ fn hide_window(gl_window: &GlutinWindowContext) {
     gl_window.window().set_visible(false);
 }
 
-fn show_window(gl_window: &GlutinWindowContext, show: bool) {
-    gl_window.window().set_visible(show);
+fn show_window(gl_window: &GlutinWindowContext, app: &mut app::App, show: bool) {
     let monitor = gl_window
         .get_focused_monitor()
         .expect("pointer is not on the monitor");
+    if show {
+        if let Err(error) = app.update_profiles(Some(&monitor)) {
+            app.set_error(Some(error.to_string()));
+        }
+    }
+    gl_window.window().set_visible(show);
     gl_window.window().set_outer_position(monitor.position());
 }
 
@@ -286,6 +329,7 @@ fn start_config_watcher(tx: &mpsc::Sender<UserEvent>) -> Result<(), Box<dyn Erro
 #[derive(Debug)]
 enum UserEvent {
     Quit,
+    Repaint,
     CliCommands(Commands),
     ConfigEvent(ConfigEvent),
 }
@@ -0,0 +1,212 @@
+use std::{fs, path::Path};
+
+/// A suggested argument list of an app
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    /// the completed part, shown in the list
+    pub label: String,
+    /// the arguments after accepting it
+    pub args: String,
+}
+
+/// Completes the typed arguments by the earlier arguments of the app,
+/// then the last word by the files (relative to the home directory) and by the launched URLs
+pub fn get_completions(
+    args: &str,
+    previous_args: &[String],
+    urls: &[String],
+    home: &Path,
+    limit: usize,
+) -> Vec<Completion> {
+    let (start, word) = split_last_word(args);
+    let replace_word = |new_word: String| Completion {
+        args: format!("{start}{new_word}"),
+        label: new_word,
+    };
+
+    let previous = previous_args
+        .iter()
+        .filter(|previous| starts_with_ignore_case(previous, args))
+        .map(|previous| Completion {
+            label: previous.clone(),
+            args: previous.clone(),
+        });
+    let paths = get_path_completions(word, home)
+        .into_iter()
+        .map(replace_word);
+    let urls = urls
+        .iter()
+        .filter(|url| !word.is_empty() && is_url_prefix(url, word))
+        .cloned()
+        .map(replace_word);
+
+    let mut completions: Vec<Completion> = Vec::new();
+    for completion in previous.chain(paths).chain(urls) {
+        if completion.args != args && !completions.contains(&completion) {
+            completions.push(completion);
+        }
+        if completions.len() == limit {
+            break;
+        }
+    }
+    completions
+}
+
+/// The files and directories the word can be completed to, the directories end with `/`.
+/// The hidden ones are only listed after a dot.
+fn get_path_completions(word: &str, home: &Path) -> Vec<String> {
+    let (directory, name) = match word.rfind('/') {
+        Some(slash) => word.split_at(slash + 1),
+        None if word.is_empty() => return vec![],
+        None => ("", word),
+    };
+    let unescaped = unescape(directory);
+    let path = match unescaped.strip_prefix('~') {
+        Some(rest) if rest.starts_with('/') => home.join(rest.trim_start_matches('/')),
+        Some(_) => return vec![],
+        None => home.join(&unescaped),
+    };
+    let name = unescape(name);
+
+    let Ok(entries) = fs::read_dir(path) else {
+        return vec![];
+    };
+    let mut completions: Vec<(String, bool)> = entries
+        .filter_map(|entry| {
+            let entry = entry.ok()?;
+            let file_name = entry.file_name().into_string().ok()?;
+            let is_hidden = file_name.starts_with('.') && !name.starts_with('.');
+            let is_matching = starts_with_ignore_case(&file_name, &name) && file_name != name;
+            (is_matching && !is_hidden).then(|| (file_name, entry.path().is_dir()))
+        })
+        .collect();
+    completions.sort_by_key(|(file_name, _)| file_name.to_lowercase());
+
+    completions
+        .into_iter()
+        .map(|(file_name, is_directory)| {
+            let slash = if is_directory { "/" } else { "" };
+            format!("{directory}{}{slash}", escape(&file_name))
+        })
+        .collect()
+}
+
+/// Whether the URL starts with the word, with or without its scheme and `www.`
+fn is_url_prefix(url: &str, word: &str) -> bool {
+    let without_scheme = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let without_www = without_scheme.trim_start_matches("www.");
+    [url, without_scheme, without_www]
+        .iter()
+        .any(|url| starts_with_ignore_case(url, word))
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.to_lowercase().starts_with(&prefix.to_lowercase())
+}
+
+/// The text before the last word of the arguments, and the last word.
+/// The spaces escaped by a backslash are part of the words.
+fn split_last_word(args: &str) -> (&str, &str) {
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in args.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            ' ' => start = i + 1,
+            _ => {}
+        }
+    }
+    args.split_at(start)
+}
+
+/// Escapes the characters which would split or quote the argument
+fn escape(file_name: &str) -> String {
+    let mut escaped = String::with_capacity(file_name.len());
+    for c in file_name.chars() {
+        if " \t\"'\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn labels(completions: Vec<Completion>) -> Vec<String> {
+        completions
+            .into_iter()
+            .map(|completion| completion.label)
+            .collect()
+    }
+
+    #[test]
+    fn test_paths() {
+        let home = env::temp_dir().join(format!("vonal-test-complete-{}", std::process::id()));
+        fs::create_dir_all(home.join("Documents/Old reports")).unwrap();
+        fs::write(home.join("Documents/report.pdf"), "").unwrap();
+        fs::write(home.join("Documents/.hidden"), "").unwrap();
+
+        let complete = |args: &str| labels(get_completions(args, &[], &[], &home, 10));
+        assert_eq!(complete("doc"), vec!["Documents/"]);
+        assert_eq!(complete("~/Doc"), vec!["~/Documents/"]);
+        assert_eq!(
+            complete("Documents/"),
+            vec!["Documents/Old\\ reports/", "Documents/report.pdf"]
+        );
+        assert_eq!(complete("Documents/."), vec!["Documents/.hidden"]);
+        assert_eq!(
+            complete("Documents/Old\\ r"),
+            vec!["Documents/Old\\ reports/"]
+        );
+        assert!(complete("Documents/report.pdf").is_empty());
+        assert!(complete("missing/").is_empty());
+
+        // the earlier words are kept
+        let completions = get_completions("--new-window Doc", &[], &[], &home, 10);
+        assert_eq!(completions[0].args, "--new-window Documents/");
+
+        fs::remove_dir_all(home).unwrap();
+    }
+
+    #[test]
+    fn test_history() {
+        let home = Path::new("/nonexistent");
+        let previous = ["--private-window".to_string(), "-P work".to_string()];
+        let urls = [
+            "https://www.github.com".to_string(),
+            "https://docs.rs".to_string(),
+        ];
+
+        let complete = |args: &str| labels(get_completions(args, &previous, &urls, home, 10));
+        assert_eq!(complete(""), vec!["--private-window", "-P work"]);
+        assert_eq!(complete("-p"), vec!["-P work"]);
+        assert_eq!(complete("--"), vec!["--private-window"]);
+        assert_eq!(complete("-P "), vec!["-P work"]);
+        assert_eq!(complete("git"), vec!["https://www.github.com"]);
+        assert_eq!(complete("https://d"), vec!["https://docs.rs"]);
+        assert_eq!(
+            get_completions("-P work docs", &previous, &urls, home, 10)[0].args,
+            "-P work https://docs.rs"
+        );
+        assert_eq!(complete("").len(), 2);
+        assert_eq!(get_completions("", &previous, &urls, home, 1).len(), 1);
+    }
+}
@@ -0,0 +1,400 @@
+use std::path::Path;
+
+use super::indexer::traits::AppIndex;
+
+/// The placeholder of the terminal command template, which is replaced by the arguments of the command
+pub const TERMINAL_COMMAND_PLACEHOLDER: &str = "{command}";
+
+/// Characters which have to be escaped by a backslash inside a quoted argument
+const QUOTED_ESCAPES: [char; 4] = ['"', '`', '$', '\\'];
+
+/// Builds the argument lists of the processes to start from an Exec value,
+/// following the Desktop Entry specification.
+///
+/// `%f` and `%u` accept a single file or URL, so the app is started once for each of them.
+/// If the Exec value has no file or URL field code, the arguments are appended to it.
+/// The arguments are split by [`split_args`] before.
+pub fn build_commands(exec: &str, args: &[String], app: &AppIndex) -> Option<Vec<Vec<String>>> {
+    let tokens = tokenize(exec)?;
+
+    let codes: Vec<char> = tokens
+        .iter()
+        .flat_map(|token| get_field_codes(token))
+        .collect();
+    let has_file_code = codes.iter().any(|code| "fFuU".contains(*code));
+    let is_single = codes.iter().any(|code| "fu".contains(*code));
+
+    if !has_file_code {
+        let mut command = expand(&tokens, &[], app);
+        command.extend(args.iter().cloned());
+        return non_empty(vec![command]);
+    }
+
+    if is_single && args.len() > 1 {
+        return non_empty(
+            args.iter()
+                .map(|arg| expand(&tokens, std::slice::from_ref(arg), app))
+                .collect(),
+        );
+    }
+
+    non_empty(vec![expand(&tokens, args, app)])
+}
+
+/// Puts the command into the terminal command template.
+/// If the template has no placeholder, the command is appended to it.
+pub fn wrap_in_terminal(template: &str, command: Vec<String>) -> Option<Vec<String>> {
+    let tokens = tokenize(template)?;
+    if !tokens
+        .iter()
+        .any(|token| token == TERMINAL_COMMAND_PLACEHOLDER)
+    {
+        return Some(tokens.into_iter().chain(command).collect());
+    }
+
+    let mut result = Vec::with_capacity(tokens.len() + command.len());
+    for token in tokens {
+        if token == TERMINAL_COMMAND_PLACEHOLDER {
+            result.extend(command.iter().cloned());
+        } else {
+            result.push(token);
+        }
+    }
+    Some(result)
+}
+
+fn non_empty(commands: Vec<Vec<String>>) -> Option<Vec<Vec<String>>> {
+    if commands.iter().any(|command| command.is_empty()) {
+        return None;
+    }
+    Some(commands)
+}
+
+/// Splits an Exec value into arguments, and removes the quoting.
+/// Returns `None` if a quote is not closed.
+pub fn tokenize(value: &str) -> Option<Vec<String>> {
+    split(unescape_string(value).into_iter())
+}
+
+/// Splits the arguments typed by the user like a shell does, without the escapes of the desktop files.
+/// Returns `None` if a quote is not closed.
+pub fn split_args(args: &str) -> Option<Vec<String>> {
+    split(args.chars())
+}
+
+fn split(chars: impl Iterator<Item = char>) -> Option<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut token: Option<String> = None;
+    let mut chars = chars.peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' | '\n' => {
+                if let Some(token) = token.take() {
+                    tokens.push(token);
+                }
+            }
+            '"' | '\'' => {
+                let quote = c;
+                let token = token.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        c if c == quote => break,
+                        '\\' if quote == '"'
+                            && chars.peek().is_some_and(|c| QUOTED_ESCAPES.contains(c)) =>
+                        {
+                            token.push(chars.next()?)
+                        }
+                        c => token.push(c),
+                    }
+                }
+            }
+            '\\' => token.get_or_insert_with(String::new).push(chars.next()?),
+            c => token.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    tokens.extend(token);
+    Some(tokens)
+}
+
+/// Quotes the argument, so [`tokenize`] gives it back as one token
+pub fn quote(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        // the backslash is escaped for both the string value type and the quoting
+        if c == '\\' {
+            quoted.push_str(r"\\\\");
+        } else {
+            if QUOTED_ESCAPES.contains(&c) {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Quotes the argument, so [`split_args`] gives it back as one token
+pub fn quote_arg(arg: &str) -> String {
+    let mut quoted = String::with_capacity(arg.len() + 2);
+    quoted.push('"');
+    for c in arg.chars() {
+        if QUOTED_ESCAPES.contains(&c) {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Applies the escapes of the string value type: `\s`, `\n`, `\t`, `\r` and `\\`.
+/// A backslash before any other char is kept, so the quoting rules can handle it.
+fn unescape_string(value: &str) -> Vec<char> {
+    let mut result = Vec::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.peek() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            _ => {
+                result.push('\\');
+                continue;
+            }
+        }
+        chars.next();
+    }
+
+    result
+}
+
+/// Replaces the field codes of the arguments.
+/// Deprecated and unknown field codes are removed.
+fn expand(tokens: &[String], files: &[String], app: &AppIndex) -> Vec<String> {
+    let mut result = Vec::with_capacity(tokens.len() + files.len());
+
+    for token in tokens {
+        match token.as_str() {
+            "%F" => result.extend(files.iter().map(|file| to_file_path(file))),
+            "%U" => result.extend(files.iter().cloned()),
+            "%i" => {
+                if let Some(icon) = &app.icon {
+                    result.push("--icon".to_string());
+                    result.push(icon.clone());
+                }
+            }
+            _ => {
+                let expanded = expand_token(token, files, app);
+                // A lone field code without a value is dropped, but an explicit empty argument is kept
+                if !expanded.is_empty() || !token.starts_with('%') {
+                    result.push(expanded);
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// The letters of the field codes in the token, without the escaped `%%`
+fn get_field_codes(token: &str) -> Vec<char> {
+    let mut codes = Vec::new();
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some('%') | None => {}
+            Some(code) => codes.push(code),
+        }
+    }
+    codes
+}
+
+fn expand_token(token: &str, files: &[String], app: &AppIndex) -> String {
+    let mut result = String::with_capacity(token.len());
+    let mut chars = token.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => result.push('%'),
+            Some('f') => result.extend(files.first().map(|file| to_file_path(file))),
+            Some('u') => result.extend(files.first().cloned()),
+            Some('c') => result.push_str(&app.name),
+            Some('k') => result.extend(
+                app.desktop_file
+                    .as_deref()
+                    .and_then(Path::to_str)
+                    .map(str::to_string),
+            ),
+            _ => {}
+        }
+    }
+
+    result
+}
+
+/// Local `file://` URLs are converted to paths, anything else is passed as it is
+fn to_file_path(file: &str) -> String {
+    file.strip_prefix("file://")
+        .map(percent_decode)
+        .unwrap_or_else(|| file.to_string())
+}
+
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let decoded = (bytes[i] == b'%')
+            .then(|| bytes.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match decoded {
+            Some(byte) => {
+                result.push(byte);
+                i += 3;
+            }
+            None => {
+                result.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&result).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn app() -> AppIndex {
+        AppIndex {
+            name: "Text Editor".to_string(),
+            icon: Some("editor".to_string()),
+            desktop_file: Some(PathBuf::from("/usr/share/applications/editor.desktop")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize(r#"sh -c "echo \"\$HOME\" \\\\ done" 'a b'"#).unwrap(),
+            vec!["sh", "-c", r#"echo "$HOME" \ done"#, "a b"]
+        );
+        assert_eq!(tokenize(r#""a\sb" c"#).unwrap(), vec!["a b", "c"]);
+        assert_eq!(tokenize(r#""" x"#).unwrap(), vec!["", "x"]);
+        assert!(tokenize(r#"echo "unclosed"#).is_none());
+
+        let arg = r#"~/my "files"/$a\s`b`"#;
+        assert_eq!(tokenize(&quote(arg)).unwrap(), vec![arg]);
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args(r#"a\sb "c\nd" 'e f' g\ h"#).unwrap(),
+            vec![r"asb", r"c\nd", "e f", "g h"]
+        );
+        assert!(split_args("what's new").is_none());
+        assert_eq!(split_args(r"what\'s new").unwrap(), vec!["what's", "new"]);
+
+        let arg = r#"~/my "files"/$a\s`b`"#;
+        assert_eq!(split_args(&quote_arg(arg)).unwrap(), vec![arg]);
+    }
+
+    fn args(args: &str) -> Vec<String> {
+        split_args(args).unwrap()
+    }
+
+    #[test]
+    fn test_field_codes() {
+        assert_eq!(
+            build_commands("editor %i --name=%c %k 100%% %d", &args(""), &app()).unwrap(),
+            vec![vec![
+                "editor",
+                "--icon",
+                "editor",
+                "--name=Text Editor",
+                "/usr/share/applications/editor.desktop",
+                "100%",
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_files() {
+        assert_eq!(
+            build_commands(
+                "editor %F",
+                &args("'a b.txt' file:///tmp/c%20d.txt"),
+                &app()
+            )
+            .unwrap(),
+            vec![vec!["editor", "a b.txt", "/tmp/c d.txt"]]
+        );
+        assert_eq!(
+            build_commands("editor %u", &args("a.txt https://b.org"), &app()).unwrap(),
+            vec![vec!["editor", "a.txt"], vec!["editor", "https://b.org"]]
+        );
+        assert_eq!(
+            build_commands("editor %f", &args(""), &app()).unwrap(),
+            vec![vec!["editor"]]
+        );
+        // an escaped percent sign is not a field code, so the arguments are appended
+        assert_eq!(
+            build_commands("printf %%f", &args("a b"), &app()).unwrap(),
+            vec![vec!["printf", "%f", "a", "b"]]
+        );
+        assert_eq!(
+            build_commands("/usr/bin/editor", &args("\"$(rm -rf ~)\" x"), &app()).unwrap(),
+            vec![vec!["/usr/bin/editor", "$(rm -rf ~)", "x"]]
+        );
+    }
+
+    #[test]
+    fn test_wrap_in_terminal() {
+        let command = vec!["htop".to_string(), "-d".to_string(), "10".to_string()];
+        assert_eq!(
+            wrap_in_terminal(
+                "alacritty --class 'float term' -e {command}",
+                command.clone()
+            )
+            .unwrap(),
+            vec![
+                "alacritty",
+                "--class",
+                "float term",
+                "-e",
+                "htop",
+                "-d",
+                "10"
+            ]
+        );
+        assert_eq!(
+            wrap_in_terminal("xterm -e", command).unwrap(),
+            vec!["xterm", "-e", "htop", "-d", "10"]
+        );
+    }
+}
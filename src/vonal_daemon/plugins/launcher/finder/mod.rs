@@ -1,71 +1,526 @@
-use super::indexer::traits::AppIndex;
+use std::collections::HashMap;
 
-mod fuzzy;
-mod limited_selection_sort;
+use super::indexer::traits::AppIndex;
+use crate::utils::search::{
+    best::Best,
+    cache::PrefixCache,
+    fuzzy::Folded,
+    query::{Field, Query, QueryMatcher},
+};
 
 #[derive(Debug)]
 pub struct AppMatch<'a> {
     pub index: &'a AppIndex,
-    fuzzy_info: fuzzy::FuzzyInfo,
+    /// The matched graphemes of the name, if the app is found by its name
+    pub positions: Vec<usize>,
 }
 
-impl<'a> PartialEq for AppMatch<'a> {
-    fn eq(&self, other: &Self) -> bool {
-        self.fuzzy_info.fitness.eq(&other.fuzzy_info.fitness)
-    }
+/// The fields the apps can be filtered by, like `cat:game`
+pub const FIELDS: [&str; 6] = ["name", "exec", "comment", "kw", "cat", "mime"];
+
+/// The main categories of the freedesktop menu specification
+pub const MAIN_CATEGORIES: [&str; 13] = [
+    "AudioVideo",
+    "Audio",
+    "Video",
+    "Development",
+    "Education",
+    "Game",
+    "Graphics",
+    "Network",
+    "Office",
+    "Science",
+    "Settings",
+    "System",
+    "Utility",
+];
+
+// The fitness penalties of the fields, so a match by name is preferred
+const GENERIC_NAME_PENALTY: i32 = 10;
+const TRANSLITERATION_PENALTY: i32 = 15;
+const EXEC_PENALTY: i32 = 10;
+const KEYWORD_PENALTY: i32 = 20;
+const CATEGORY_PENALTY: i32 = 30;
+const COMMENT_PENALTY: i32 = 40;
+const MIME_TYPE_PENALTY: i32 = 40;
+
+/// A query equal to an alias puts its app first
+const EXACT_ALIAS_BONUS: i32 = 1_000_000;
+/// Worth about two matching characters, so pinned apps win the close matches
+const PINNED_BONUS: i32 = 2000;
+
+/// The user's adjustments of the search. Apps are referred by their name or their .desktop file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Preferences {
+    /// alias -> app
+    pub aliases: HashMap<String, String>,
+    /// apps which are never found
+    pub blacklist: Vec<String>,
+    /// apps which are preferred over the others, in the order of preference
+    pub pinned: Vec<String>,
+    /// whether `e` matches `é`
+    pub ignore_accents: bool,
+    /// whether `chormium` finds `chromium`
+    pub tolerate_typos: bool,
 }
-impl<'a> PartialOrd for AppMatch<'a> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.fuzzy_info
-            .fitness
-            .partial_cmp(&other.fuzzy_info.fitness)
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            aliases: HashMap::new(),
+            blacklist: vec![],
+            pinned: vec![],
+            ignore_accents: true,
+            tolerate_typos: true,
+        }
     }
 }
 
+/// An app and what the preferences say about it
+struct Entry {
+    app: AppIndex,
+    aliases: Vec<String>,
+    pin: Option<usize>,
+    /// The texts the app is found by, the name first
+    fields: Vec<Field>,
+}
+
+/// The result of a query
+struct Search {
+    /// every matching entry, a query extending this one can only match these
+    candidates: Vec<usize>,
+    number_of_results: usize,
+    /// the best entries
+    results: Vec<usize>,
+}
+
 #[derive(Default)]
 pub struct Finder {
-    cache: Vec<AppIndex>,
+    entries: Vec<Entry>,
+    ignore_accents: bool,
+    tolerate_typos: bool,
+    /// the recent searches by their folded query
+    searches: PrefixCache<u32, Search>,
 }
 
 impl Finder {
-    pub fn new(indices: Vec<AppIndex>) -> Self {
-        Self { cache: indices }
+    pub fn new(indices: Vec<AppIndex>, preferences: &Preferences) -> Self {
+        let entries = indices
+            .into_iter()
+            .filter(|app| {
+                !preferences
+                    .blacklist
+                    .iter()
+                    .any(|reference| app.is_referred_by(reference))
+            })
+            .map(|app| {
+                let aliases: Vec<String> = preferences
+                    .aliases
+                    .iter()
+                    .filter(|(_, reference)| app.is_referred_by(reference))
+                    .map(|(alias, _)| alias.clone())
+                    .collect();
+                Entry {
+                    fields: get_fields(&app, &aliases)
+                        .map(|(name, text, penalty)| Field {
+                            name,
+                            text: Folded::new(text, preferences.ignore_accents),
+                            penalty,
+                        })
+                        .collect(),
+                    aliases,
+                    pin: preferences
+                        .pinned
+                        .iter()
+                        .position(|reference| app.is_referred_by(reference)),
+                    app,
+                }
+            })
+            .collect();
+        Self {
+            entries,
+            ignore_accents: preferences.ignore_accents,
+            tolerate_typos: preferences.tolerate_typos,
+            searches: Default::default(),
+        }
     }
 
-    pub fn find(&self, query: &str, number_of_results: usize) -> Vec<AppMatch<'_>> {
-        let mut results: Vec<_> = self
-            .cache
-            .iter()
-            .map(|app| {
-                let generic_name = app.generic_name.clone().unwrap_or_default();
-                let name_match = fuzzy::get_fuzzy_info(query, &app.name);
-                let mut generic_name_match = fuzzy::get_fuzzy_info(query, &generic_name);
-                let mut exec_match = fuzzy::get_fuzzy_info(query, &app.exec);
-
-                // 1. match by name is preferred
-                generic_name_match.fitness -= 10;
-                exec_match.fitness -= 10;
-
-                let mut fuzzy_info = [name_match, generic_name_match, exec_match]
-                    .into_iter()
-                    .max_by_key(|i| i.fitness)
-                    .unwrap();
-
-                // 2. quality correction
-                let has_action = !app.actions.is_empty();
-                let has_generic_name = app.generic_name.is_some();
-                fuzzy_info.fitness += if has_action { 10 } else { 0 };
-                fuzzy_info.fitness += if has_generic_name { 10 } else { 0 };
+    /// The best matching apps, the best first. The query can have the operators of `search::query`.
+    /// A query typed further only checks the apps matching the recent query it extends.
+    pub fn find(&mut self, query: &str, number_of_results: usize) -> Vec<AppMatch<'_>> {
+        let mut matcher = QueryMatcher::new(&Query::parse(query, &FIELDS), self.ignore_accents)
+            .with_typos(self.tolerate_typos);
+        if matcher.is_empty() || number_of_results == 0 {
+            return vec![];
+        }
 
+        let key = matcher.key().to_vec();
+        let results = match self.searches.get(&key) {
+            Some(search) if search.number_of_results == number_of_results => search.results.clone(),
+            _ => {
+                let candidates = match self.searches.get_longest_prefix(&key) {
+                    Some(search) => search.candidates.clone(),
+                    // the apps which are not listed are only there to open files and URLs
+                    None => (0..self.entries.len())
+                        .filter(|i| !self.entries[*i].app.no_display)
+                        .collect(),
+                };
+                let search = self.search(&mut matcher, query, candidates, number_of_results);
+                let results = search.results.clone();
+                self.searches.insert(key, search);
+                results
+            }
+        };
+
+        results
+            .into_iter()
+            .map(|i| {
+                let entry = &self.entries[i];
                 AppMatch {
-                    index: app,
-                    fuzzy_info,
+                    index: &entry.app,
+                    positions: matcher.get_positions(&entry.fields, 0),
                 }
             })
-            .collect();
+            .collect()
+    }
+
+    fn search(
+        &self,
+        matcher: &mut QueryMatcher,
+        query: &str,
+        candidates: Vec<usize>,
+        number_of_results: usize,
+    ) -> Search {
+        let mut matching = Vec::with_capacity(candidates.len());
+        let mut best = Best::new(number_of_results);
+        for i in candidates {
+            if let Some(fitness) = get_fitness(matcher, &self.entries[i], query) {
+                matching.push(i);
+                best.push(fitness, i);
+            }
+        }
 
-        limited_selection_sort::sort(&mut results, number_of_results);
-        results.truncate(number_of_results);
+        Search {
+            candidates: matching,
+            number_of_results,
+            results: best.into_sorted_vec(),
+        }
+    }
+
+    /// Every app, except the blacklisted ones, including the ones which are not listed
+    pub fn get_apps(&self) -> Vec<&AppIndex> {
+        self.entries.iter().map(|entry| &entry.app).collect()
+    }
+
+    /// Returns the apps of the category in alphabetical order
+    pub fn find_by_category(&self, category: &str) -> Vec<&AppIndex> {
+        let mut results: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| &entry.app)
+            .filter(|app| {
+                !app.no_display
+                    && app
+                        .categories
+                        .iter()
+                        .any(|app_category| app_category.eq_ignore_ascii_case(category))
+            })
+            .collect();
+        results.sort_by_cached_key(|app| app.name.to_lowercase());
         results
     }
 }
+
+/// The texts of the app with their field names and fitness penalties, the name first
+fn get_fields<'a>(
+    app: &'a AppIndex,
+    aliases: &'a [String],
+) -> impl Iterator<Item = (&'static str, &'a str, i32)> + 'a {
+    // 1. match by name is preferred, in either language, or by an alias
+    let names = [Some(&app.name), app.untranslated_name.as_ref()]
+        .into_iter()
+        .flatten()
+        .chain(aliases)
+        .map(|name| ("name", name.as_str(), 0));
+    // typed on a Latin layout
+    let transliterations = app
+        .transliterations
+        .iter()
+        .map(|name| ("name", name.as_str(), TRANSLITERATION_PENALTY));
+    let others = [
+        ("name", app.generic_name.as_ref(), GENERIC_NAME_PENALTY),
+        ("exec", Some(&app.exec), EXEC_PENALTY),
+        ("comment", app.comment.as_ref(), COMMENT_PENALTY),
+    ]
+    .into_iter()
+    .filter_map(|(name, text, penalty)| Some((name, text?.as_str(), penalty)));
+    let keywords = app
+        .keywords
+        .iter()
+        .map(|keyword| ("kw", keyword.as_str(), KEYWORD_PENALTY));
+    let categories = app
+        .categories
+        .iter()
+        .map(|category| ("cat", category.as_str(), CATEGORY_PENALTY));
+    // only the subtype, so `pdf` matches `application/pdf` without a late match
+    let mime_types = app.mime_types.iter().map(|mime_type| {
+        let subtype = mime_type.rsplit('/').next().unwrap_or(mime_type);
+        ("mime", subtype, MIME_TYPE_PENALTY)
+    });
+
+    names
+        .chain(transliterations)
+        .chain(others)
+        .chain(keywords)
+        .chain(categories)
+        .chain(mime_types)
+}
+
+/// The fitness of the app, or `None` if it doesn't match the query
+fn get_fitness(matcher: &mut QueryMatcher, entry: &Entry, query: &str) -> Option<i32> {
+    let Entry {
+        app,
+        aliases,
+        pin,
+        fields,
+    } = entry;
+
+    let mut fitness = matcher.get_fitness(fields)?;
+
+    // 2. quality correction
+    let has_action = !app.actions.is_empty();
+    let has_generic_name = app.generic_name.is_some();
+    fitness += if has_action { 10 } else { 0 };
+    fitness += if has_generic_name { 10 } else { 0 };
+
+    // 3. preferences of the user
+    if aliases
+        .iter()
+        .any(|alias| alias.eq_ignore_ascii_case(query))
+    {
+        fitness += EXACT_ALIAS_BONUS;
+    }
+    if let (Some(pin), true) = (pin, fitness > 0) {
+        // the earlier pins are preferred
+        fitness += PINNED_BONUS - *pin as i32;
+    }
+
+    Some(fitness)
+}
+
+/// Returns the main categories starting with the query
+pub fn find_main_categories(query: &str) -> Vec<&'static str> {
+    let query = query.to_lowercase();
+    MAIN_CATEGORIES
+        .into_iter()
+        .filter(|category| category.to_lowercase().starts_with(&query))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, keywords: &[&str], categories: &[&str], mime_types: &[&str]) -> AppIndex {
+        let to_strings = |texts: &[&str]| texts.iter().map(|text| text.to_string()).collect();
+        AppIndex {
+            exec: name.to_lowercase(),
+            name: name.to_string(),
+            keywords: to_strings(keywords),
+            categories: to_strings(categories),
+            mime_types: to_strings(mime_types),
+            ..Default::default()
+        }
+    }
+
+    fn finder() -> Finder {
+        finder_with(&Preferences::default())
+    }
+
+    fn finder_with(preferences: &Preferences) -> Finder {
+        Finder::new(
+            vec![
+                app(
+                    "Firefox",
+                    &["Internet", "WWW", "Browser"],
+                    &["Network", "WebBrowser"],
+                    &["text/html"],
+                ),
+                app(
+                    "Evince",
+                    &["PDF", "PostScript"],
+                    &["Office", "Viewer"],
+                    &["application/pdf"],
+                ),
+                app(
+                    "Code",
+                    &["editor"],
+                    &["Development", "IDE"],
+                    &["text/plain"],
+                ),
+                app("Brasero", &[], &["AudioVideo", "DiscBurning"], &[]),
+            ],
+            preferences,
+        )
+    }
+
+    #[test]
+    fn test_find_by_secondary_fields() {
+        let mut finder = finder();
+        assert_eq!(finder.find("browser", 1)[0].index.name, "Firefox");
+        assert_eq!(finder.find("pdf", 1)[0].index.name, "Evince");
+        assert_eq!(finder.find("ide", 1)[0].index.name, "Code");
+        // the name is preferred
+        assert_eq!(finder.find("bra", 1)[0].index.name, "Brasero");
+    }
+
+    #[test]
+    fn test_find_by_category() {
+        let finder = finder();
+        let names: Vec<_> = finder
+            .find_by_category("development")
+            .into_iter()
+            .map(|app| app.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Code"]);
+        assert_eq!(find_main_categories("au"), vec!["AudioVideo", "Audio"]);
+    }
+
+    #[test]
+    fn test_preferences() {
+        let preferences = Preferences {
+            aliases: HashMap::from([("web".to_string(), "firefox".to_string())]),
+            blacklist: vec!["Brasero".to_string()],
+            pinned: vec!["Code".to_string()],
+            ..Default::default()
+        };
+        let mut finder = finder_with(&preferences);
+
+        assert_eq!(finder.find("web", 1)[0].index.name, "Firefox");
+        assert!(finder
+            .find("bra", 3)
+            .iter()
+            .all(|app_match| app_match.index.name != "Brasero"));
+        assert!(finder.find_by_category("AudioVideo").is_empty());
+        // "e" matches Evince earlier, but Code is pinned
+        assert_eq!(finder.find("e", 1)[0].index.name, "Code");
+    }
+
+    #[test]
+    fn test_no_display() {
+        let handler = AppIndex {
+            no_display: true,
+            ..app(
+                "Code URL Handler",
+                &[],
+                &["Development"],
+                &["x-scheme-handler/vscode"],
+            )
+        };
+        let mut finder = Finder::new(vec![handler], &Preferences::default());
+
+        assert!(finder.find("code", 1).is_empty());
+        assert!(finder.find_by_category("Development").is_empty());
+        // it can still open the URLs
+        assert_eq!(finder.get_apps()[0].name, "Code URL Handler");
+    }
+
+    #[test]
+    fn test_find() {
+        let mut finder = finder();
+        // more results than apps, the better matches first
+        let names: Vec<_> = finder
+            .find("o", 10)
+            .into_iter()
+            .map(|app_match| app_match.index.name.as_str())
+            .collect();
+        // the start of the Office category wins over the middle of the names
+        assert_eq!(names, vec!["Evince", "Code", "Firefox", "Brasero"]);
+
+        let results = finder.find("fox", 1);
+        assert_eq!(results[0].index.name, "Firefox");
+        assert_eq!(results[0].positions, vec![0, 5, 6]);
+        // found by a keyword, not by the name
+        assert!(finder.find("postscript", 1)[0].positions.is_empty());
+
+        assert!(finder.find("qqq", 5).is_empty());
+        assert!(finder.find("", 5).is_empty());
+        assert!(finder.find("code", 0).is_empty());
+    }
+
+    #[test]
+    fn test_transliterations() {
+        let apps = ["Терминал", "Terminal", "终端", "テキスト"].map(|name| {
+            let mut app = app(name, &[], &[], &[]);
+            app.transliterations = app.get_transliterations();
+            app
+        });
+        let mut finder = Finder::new(apps.to_vec(), &Preferences::default());
+        let mut names = |query: &str| -> Vec<String> {
+            finder
+                .find(query, 2)
+                .into_iter()
+                .map(|app_match| app_match.index.name.clone())
+                .collect()
+        };
+
+        // the Latin name is preferred
+        assert_eq!(names("terminal"), vec!["Terminal", "Терминал"]);
+        assert_eq!(names("zhongduan")[0], "终端");
+        assert_eq!(names("duan")[0], "终端");
+        assert_eq!(names("tekisuto")[0], "テキスト");
+        // the original script is found as before
+        assert_eq!(names("терм")[0], "Терминал");
+    }
+
+    #[test]
+    fn test_operators() {
+        let mut finder = finder();
+        let mut names = |query: &str| -> Vec<String> {
+            finder
+                .find(query, 10)
+                .into_iter()
+                .map(|app_match| app_match.index.name.clone())
+                .collect()
+        };
+
+        assert_eq!(names("cat:'office"), vec!["Evince"]);
+        assert_eq!(names("cat:office")[0], "Evince");
+        assert_eq!(names("exec:^code"), vec!["Code"]);
+        assert_eq!(names("^fire"), vec!["Firefox"]);
+        assert_eq!(names("^code$"), vec!["Code"]);
+        assert_eq!(names("'rows"), vec!["Firefox"]);
+        assert_eq!(names("^fire | ^bras"), vec!["Firefox", "Brasero"]);
+        assert_eq!(names("^fire | ^bras !fox"), vec!["Brasero"]);
+        assert_eq!(names("!fox").len(), 3);
+    }
+
+    #[test]
+    fn test_incremental_search() {
+        let names = |finder: &mut Finder, query: &str| -> Vec<String> {
+            finder
+                .find(query, 3)
+                .into_iter()
+                .map(|app_match| app_match.index.name.clone())
+                .collect()
+        };
+
+        // typing, backspacing and typing again gives the same as searching at once
+        let mut finder = finder();
+        for query in [
+            "e", "ed", "edi", "ed", "e", "ev", "evince", "p", "pd", "pdf",
+        ] {
+            assert_eq!(names(&mut finder, query), names(&mut self::finder(), query));
+        }
+        // only Evince and Code have a `p`, the longer queries start from them
+        let candidates = |finder: &mut Finder, query: &str| {
+            let key = QueryMatcher::new(&Query::parse(query, &FIELDS), true)
+                .key()
+                .to_vec();
+            finder.searches.get(&key).unwrap().candidates.clone()
+        };
+        assert_eq!(candidates(&mut finder, "e").len(), 4);
+        assert_eq!(candidates(&mut finder, "p"), vec![1, 2]);
+        assert_eq!(candidates(&mut finder, "pdf"), vec![1, 2]);
+    }
+}
@@ -1,39 +1,216 @@
-use super::traits::{AppAction, AppIndex};
-use freedesktop_desktop_entry::{default_paths, DesktopEntry, Iter};
-use std::fs;
+use super::{
+    locale::{get_locales, get_localized},
+    traits::{AppAction, AppIndex, Sandbox},
+};
+use freedesktop_desktop_entry::{DesktopEntry, Iter};
+use std::{
+    borrow::Cow,
+    env, fs,
+    os::unix::prelude::PermissionsExt,
+    path::{Path, PathBuf},
+};
 
 /**
- * Creates application indexes from .desktop files
+ * Creates application indexes from the .desktop files of a directory and its subdirectories.
+ * The hidden files are returned separately, because they still hide the files of their desktop ID
+ * in the later directories.
  */
-pub fn index() -> Vec<AppIndex> {
-    Iter::new(default_paths())
-        .filter_map(|path| {
-            fs::read_to_string(&path)
-                .ok()
-                .as_ref()
-                .and_then(|bytes| DesktopEntry::decode(&path, bytes).ok())
-                .and_then(|entry| {
-                    let locale = None;
-                    Some(AppIndex {
-                        name: entry.name(locale)?.to_string(),
-                        exec: entry.exec()?.into(),
-                        generic_name: entry.generic_name(locale).map(|s| s.to_string()),
-                        actions: entry
-                            .actions()
-                            .map(|actions| {
-                                actions
-                                    .split(';')
-                                    .filter_map(|action| {
-                                        Some(AppAction {
-                                            name: entry.action_name(action, None)?.to_string(),
-                                            command: entry.action_exec(action)?.to_string(),
-                                        })
-                                    })
-                                    .collect::<Vec<_>>()
-                            })
-                            .unwrap_or_default(),
+pub fn index(directory: &Path) -> (Vec<AppIndex>, Vec<PathBuf>) {
+    let current_desktops = get_current_desktops();
+    let locales = get_locales();
+
+    let mut apps = Vec::new();
+    let mut hidden = Vec::new();
+    for path in Iter::new(vec![directory.to_path_buf()]) {
+        let Ok(bytes) = fs::read_to_string(&path) else {
+            continue;
+        };
+        let Ok(entry) = DesktopEntry::decode(&path, &bytes) else {
+            continue;
+        };
+        if !is_visible(&entry, &current_desktops) {
+            hidden.push(path);
+        } else if let Some(app) = to_app_index(&entry, &path, &locales) {
+            apps.push(app);
+        }
+    }
+    (apps, hidden)
+}
+
+fn to_app_index(entry: &DesktopEntry, path: &Path, locales: &[String]) -> Option<AppIndex> {
+    let name = get_localized(entry, "Name", locales)?.to_string();
+    let untranslated_name = entry.name(None)?;
+    let mut app = AppIndex {
+        untranslated_name: (untranslated_name != name).then(|| untranslated_name.to_string()),
+        transliterations: vec![],
+        name,
+        exec: entry.exec()?.into(),
+        generic_name: get_localized(entry, "GenericName", locales).map(|s| s.to_string()),
+        comment: get_localized(entry, "Comment", locales).map(|s| s.to_string()),
+        keywords: get_keywords(entry, locales),
+        categories: split_list(entry.categories()),
+        mime_types: split_list(entry.mime_type()),
+        icon: entry.icon().map(|s| s.to_string()),
+        desktop_file: Some(path.to_path_buf()),
+        terminal: entry.terminal(),
+        working_directory: entry
+            .desktop_entry("Path")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from),
+        startup_notify: entry.startup_notify(),
+        startup_wm_class: entry.desktop_entry("StartupWMClass").map(|s| s.to_string()),
+        sandbox: get_sandbox(entry, path),
+        no_display: entry.no_display(),
+        actions: entry
+            .actions()
+            .map(|actions| {
+                actions
+                    .split(';')
+                    .filter_map(|action| {
+                        Some(AppAction {
+                            name: get_action_name(entry, action, locales)?,
+                            command: entry.action_exec(action)?.to_string(),
+                        })
                     })
-                })
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default(),
+    };
+    app.transliterations = app.get_transliterations();
+    Some(app)
+}
+
+/// Both the translated and the untranslated keywords, so either language can be searched for
+fn get_keywords(entry: &DesktopEntry, locales: &[String]) -> Vec<String> {
+    let mut keywords: Vec<String> = get_localized(entry, "Keywords", locales)
+        .into_iter()
+        .chain(entry.desktop_entry("Keywords").map(Cow::Borrowed))
+        .flat_map(|keywords| split_list(Some(&keywords)))
+        .collect();
+    keywords.sort();
+    keywords.dedup();
+    keywords
+}
+
+/// Splits a semicolon separated list
+fn split_list(list: Option<&str>) -> Vec<String> {
+    list.map(|list| {
+        list.split(';')
+            .filter(|item| !item.is_empty())
+            .map(str::to_string)
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+fn get_action_name(entry: &DesktopEntry, action: &str, locales: &[String]) -> Option<String> {
+    let untranslated = entry.action_name(action, None)?;
+    Some(
+        locales
+            .iter()
+            .filter_map(|locale| entry.action_name(action, Some(locale)))
+            .find(|name| *name != untranslated)
+            .unwrap_or(untranslated)
+            .to_string(),
+    )
+}
+
+/// Flatpak and Snap mark the entries they export, and they are in their export directories
+fn get_sandbox(entry: &DesktopEntry, path: &Path) -> Option<Sandbox> {
+    let path = path.to_string_lossy();
+    if entry.desktop_entry("X-Flatpak").is_some() || path.contains("/flatpak/exports/") {
+        Some(Sandbox::Flatpak)
+    } else if entry.desktop_entry("X-SnapInstanceName").is_some()
+        || path.contains("/snapd/desktop/")
+    {
+        Some(Sandbox::Snap)
+    } else {
+        None
+    }
+}
+
+/// `XDG_CURRENT_DESKTOP` is a colon separated list, like `ubuntu:GNOME`
+pub fn get_current_desktops() -> Vec<String> {
+    env::var("XDG_CURRENT_DESKTOP")
+        .map(|desktops| {
+            desktops
+                .split(':')
+                .filter(|desktop| !desktop.is_empty())
+                .map(str::to_string)
+                .collect()
         })
-        .collect()
+        .unwrap_or_default()
+}
+
+/// Applies the rules of the spec for the entries which are not there for the user.
+/// `NoDisplay` entries are kept, because they can still open files and URLs.
+fn is_visible(entry: &DesktopEntry, current_desktops: &[String]) -> bool {
+    let is_hidden = entry.desktop_entry("Hidden") == Some("true");
+
+    !is_hidden
+        && is_shown_in(entry.only_show_in(), entry.not_show_in(), current_desktops)
+        && entry.desktop_entry("TryExec").is_none_or(is_installed)
+}
+
+/// `OnlyShowIn` and `NotShowIn` are semicolon separated lists of desktop names
+fn is_shown_in(
+    only_show_in: Option<&str>,
+    not_show_in: Option<&str>,
+    current_desktops: &[String],
+) -> bool {
+    let contains_current = |desktops: &str| {
+        desktops
+            .split(';')
+            .any(|desktop| current_desktops.iter().any(|current| current == desktop))
+    };
+
+    match (only_show_in, not_show_in) {
+        (Some(only_show_in), _) => contains_current(only_show_in),
+        (None, Some(not_show_in)) => !contains_current(not_show_in),
+        (None, None) => true,
+    }
+}
+
+/// Checks if the program exists and it's executable, searching the PATH for relative names
+fn is_installed(program: &str) -> bool {
+    let is_executable = |path: &Path| {
+        fs::metadata(path)
+            .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+
+    if program.contains('/') {
+        return is_executable(Path::new(program));
+    }
+
+    env::var("PATH")
+        .map(|path| {
+            path.split(':')
+                .any(|dir| is_executable(&Path::new(dir).join(program)))
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_shown_in() {
+        let current = vec!["ubuntu".to_string(), "GNOME".to_string()];
+
+        assert!(is_shown_in(None, None, &current));
+        assert!(is_shown_in(Some("KDE;GNOME;"), None, &current));
+        assert!(!is_shown_in(Some("KDE;"), None, &current));
+        assert!(!is_shown_in(None, Some("GNOME;"), &current));
+        assert!(is_shown_in(None, Some("KDE;"), &current));
+        assert!(!is_shown_in(Some("GNOME;"), None, &[]));
+    }
+
+    #[test]
+    fn test_is_installed() {
+        assert!(is_installed("/bin/sh"));
+        assert!(!is_installed("/nonexistent/vonal"));
+        assert!(!is_installed("vonal-surely-not-installed"));
+    }
 }
@@ -0,0 +1,78 @@
+use std::{borrow::Cow, env};
+
+use freedesktop_desktop_entry::DesktopEntry;
+
+/// Returns the locales to try for localized keys, from the most specific to the least specific one.
+///
+/// The locale comes from `LC_ALL`, `LC_MESSAGES` or `LANG`, in this order.
+pub fn get_locales() -> Vec<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|locale| !locale.is_empty())
+        .map(|locale| get_locale_chain(&locale))
+        .unwrap_or_default()
+}
+
+/// Follows the matching rules of the Desktop Entry specification:
+/// `lang_COUNTRY.ENCODING@MODIFIER` matches `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`, `lang@MODIFIER` and `lang`.
+/// The encoding is always ignored.
+fn get_locale_chain(locale: &str) -> Vec<String> {
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale, None),
+    };
+    let locale = locale.split('.').next().unwrap_or_default();
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+
+    if lang.is_empty() || lang == "C" || lang == "POSIX" {
+        return vec![];
+    }
+
+    let mut chain = Vec::with_capacity(4);
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        chain.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        chain.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        chain.push(format!("{lang}@{modifier}"));
+    }
+    chain.push(lang.to_string());
+    chain
+}
+
+/// Returns the value for the first locale which has a translation, or the untranslated value
+pub fn get_localized<'a>(
+    entry: &'a DesktopEntry<'a>,
+    key: &str,
+    locales: &[String],
+) -> Option<Cow<'a, str>> {
+    let untranslated = entry.desktop_entry(key);
+    locales
+        .iter()
+        .filter_map(|locale| entry.desktop_entry_localized(key, Some(locale)))
+        .find(|value| Some(value.as_ref()) != untranslated)
+        .or_else(|| untranslated.map(Cow::Borrowed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_chain() {
+        assert_eq!(
+            get_locale_chain("sr_YU.UTF-8@Latn"),
+            vec!["sr_YU@Latn", "sr_YU", "sr@Latn", "sr"]
+        );
+        assert_eq!(get_locale_chain("hu_HU.UTF-8"), vec!["hu_HU", "hu"]);
+        assert_eq!(get_locale_chain("de"), vec!["de"]);
+        assert!(get_locale_chain("C.UTF-8").is_empty());
+        assert!(get_locale_chain("POSIX").is_empty());
+    }
+}
@@ -0,0 +1,208 @@
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use super::{desktop::get_current_desktops, traits::AppIndex};
+use crate::plugins::launcher::xdg::get_data_directories;
+
+/// The associations of the `mimeapps.list` files between the MIME types and the desktop IDs of the apps
+#[derive(Default)]
+pub struct MimeApps {
+    defaults: HashMap<String, Vec<String>>,
+    added: HashMap<String, Vec<String>>,
+    removed: HashMap<String, Vec<String>>,
+}
+
+impl MimeApps {
+    /// Reads every `mimeapps.list`, the ones of the user and of the current desktop take precedence
+    pub fn load() -> Self {
+        let mut mime_apps = Self::default();
+        for path in get_paths() {
+            if let Ok(content) = fs::read_to_string(path) {
+                mime_apps.add(&content);
+            }
+        }
+        mime_apps
+    }
+
+    /// The groups of the file are appended to the ones of the files with higher precedence
+    fn add(&mut self, content: &str) {
+        let mut group = None;
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                group = match name {
+                    "Default Applications" => Some(&mut self.defaults),
+                    "Added Associations" => Some(&mut self.added),
+                    "Removed Associations" => Some(&mut self.removed),
+                    _ => None,
+                };
+                continue;
+            }
+
+            let (Some(group), Some((mime_type, ids))) = (group.as_mut(), line.split_once('='))
+            else {
+                continue;
+            };
+            let associations = group.entry(mime_type.trim().to_string()).or_default();
+            for id in ids.split(';').map(str::trim) {
+                if !id.is_empty() && !associations.iter().any(|existing| existing == id) {
+                    associations.push(id.to_string());
+                }
+            }
+        }
+    }
+
+    /// The apps which can open any of the types, the default one first.
+    /// The types are in the order of preference, like the results of `mime::Database::get_broader_types`.
+    pub fn get_handlers<'a>(
+        &self,
+        mime_types: &[String],
+        apps: &[&'a AppIndex],
+    ) -> Vec<&'a AppIndex> {
+        let by_id: HashMap<String, &AppIndex> = apps
+            .iter()
+            .filter_map(|app| Some((app.desktop_id()?, *app)))
+            .collect();
+        let is_removed = |mime_type: &String, id: &String| {
+            self.removed
+                .get(mime_type)
+                .is_some_and(|removed| removed.contains(id))
+        };
+        let get_associated = |associations: &HashMap<String, Vec<String>>| {
+            mime_types
+                .iter()
+                .flat_map(|mime_type| {
+                    associations
+                        .get(mime_type)
+                        .into_iter()
+                        .flatten()
+                        .filter(|id| !is_removed(mime_type, id))
+                        .filter_map(|id| by_id.get(id).copied())
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let default = get_associated(&self.defaults).into_iter().next();
+        let added = get_associated(&self.added);
+        let mut capable: Vec<&AppIndex> = mime_types
+            .iter()
+            .flat_map(|mime_type| {
+                apps.iter().copied().filter(|app| {
+                    app.mime_types.contains(mime_type)
+                        && !app
+                            .desktop_id()
+                            .is_some_and(|id| is_removed(mime_type, &id))
+                })
+            })
+            .collect();
+        capable.sort_by_cached_key(|app| app.name.to_lowercase());
+
+        let mut handlers: Vec<&AppIndex> = Vec::new();
+        for app in default.into_iter().chain(added).chain(capable) {
+            if !handlers.iter().any(|handler| std::ptr::eq(*handler, app)) {
+                handlers.push(app);
+            }
+        }
+        handlers
+    }
+}
+
+/// The `mimeapps.list` files in the order of precedence, the desktop specific ones first in each directory
+fn get_paths() -> Vec<PathBuf> {
+    let config_home = env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env::var("HOME").unwrap_or_default()).join(".config"));
+    let config_directories = env::var("XDG_CONFIG_DIRS")
+        .ok()
+        .filter(|paths| !paths.is_empty())
+        .unwrap_or_else(|| "/etc/xdg".to_string());
+    let application_directories = get_data_directories()
+        .into_iter()
+        .map(|directory| directory.join("applications"));
+
+    let directories = [config_home]
+        .into_iter()
+        .chain(
+            config_directories
+                .split(':')
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+        )
+        .chain(application_directories);
+    let desktops: Vec<String> = get_current_desktops()
+        .iter()
+        .map(|desktop| desktop.to_lowercase())
+        .collect();
+
+    directories
+        .flat_map(|directory| {
+            desktops
+                .iter()
+                .map(|desktop| directory.join(format!("{desktop}-mimeapps.list")))
+                .chain([directory.join("mimeapps.list")])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn app(id: &str, mime_types: &[&str]) -> AppIndex {
+        AppIndex {
+            exec: id.to_string(),
+            name: id.to_string(),
+            mime_types: mime_types.iter().map(|t| t.to_string()).collect(),
+            desktop_file: Some(Path::new("/usr/share/applications").join(format!("{id}.desktop"))),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_get_handlers() {
+        let mut mime_apps = MimeApps::default();
+        mime_apps.add(
+            "[Default Applications]\n\
+            application/pdf=missing.desktop;okular.desktop\n\
+            [Added Associations]\n\
+            text/plain=gimp.desktop;\n\
+            [Removed Associations]\n\
+            application/pdf=firefox.desktop\n",
+        );
+        // the lower precedence default comes after the first one
+        mime_apps.add("[Default Applications]\napplication/pdf=evince.desktop\n");
+
+        let apps = [
+            app("evince", &["application/pdf"]),
+            app("firefox", &["application/pdf", "text/html"]),
+            app("okular", &["application/pdf"]),
+            app("gimp", &["image/png"]),
+            app("code", &["text/plain"]),
+        ];
+        let apps: Vec<&AppIndex> = apps.iter().collect();
+        let names = |mime_types: &[&str]| {
+            let mime_types: Vec<String> = mime_types.iter().map(|t| t.to_string()).collect();
+            mime_apps
+                .get_handlers(&mime_types, &apps)
+                .into_iter()
+                .map(|app| app.name.as_str())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&["application/pdf"]), vec!["okular", "evince"]);
+        assert_eq!(
+            names(&["text/x-python", "text/plain"]),
+            vec!["gimp", "code"]
+        );
+        assert!(names(&["video/mp4"]).is_empty());
+    }
+}
@@ -1,44 +1,419 @@
-use egui::epaint::ahash::{HashMap, HashMapExt};
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
 
-use self::traits::AppIndex;
+use freedesktop_desktop_entry::default_paths;
+use serde::{Deserialize, Serialize};
+
+use self::{
+    traits::{get_desktop_id, AppIndex},
+    user::UserEntry,
+};
+use super::{exec, xdg::get_data_home};
+
+/// The exported apps of Flatpak and Snap. Their directories are missing from `XDG_DATA_DIRS`
+/// if the session was not started through their profile scripts.
+const SYSTEM_SANDBOX_ROOTS: [&str; 2] = [
+    "/var/lib/flatpak/exports/share/applications",
+    "/var/lib/snapd/desktop/applications",
+];
 
 pub mod desktop;
+mod locale;
+pub mod mimeapps;
 pub mod path;
+mod store;
 pub mod traits;
+pub mod user;
+pub mod watcher;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SourceKind {
+    /// A directory of .desktop files, indexed recursively
+    Desktop,
+    /// A directory of the PATH
+    Path,
+}
+
+/// The apps found in one directory, and the modification time they were indexed at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Source {
+    pub root: PathBuf,
+    pub kind: SourceKind,
+    pub mtime: Option<SystemTime>,
+    pub apps: Vec<AppIndex>,
+    /// The desktop files which are not shown, like the `Hidden` overrides of the user
+    pub hidden: Vec<PathBuf>,
+}
 
 #[derive(Default)]
-pub struct Indexer {}
+pub struct Indexer {
+    /// in the order of precedence, desktop sources come first
+    sources: Vec<Source>,
+    /// the entries of the config, they take precedence over every source
+    entries: Vec<UserEntry>,
+}
 
 impl Indexer {
-    /// index apps with multiple indexers, and deduplicate the result
-    pub fn index(&self, index_path: bool) -> Vec<AppIndex> {
-        let desktop_indices = desktop::index();
-        if !index_path {
-            return desktop_indices;
+    /// Loads the index saved by the last run. The stale sources are refreshed by [`Indexer::refresh`].
+    pub fn load(index_path: bool, entries: Vec<UserEntry>) -> Self {
+        let mut stored_sources = store::load(&store::get_store_path());
+        let sources = get_roots(index_path)
+            .into_iter()
+            .map(|(root, kind)| {
+                stored_sources
+                    .iter()
+                    .position(|source| source.root == root && source.kind == kind)
+                    .map(|position| stored_sources.swap_remove(position))
+                    .unwrap_or(Source {
+                        root,
+                        kind,
+                        mtime: None,
+                        apps: vec![],
+                        hidden: vec![],
+                    })
+            })
+            .collect();
+
+        Self { sources, entries }
+    }
+
+    /// Reindexes the sources modified since they were indexed, or every source if it's forced.
+    /// Returns whether anything has changed.
+    pub fn refresh(&mut self, force: bool) -> bool {
+        let mut changed = false;
+        for (root, _) in self.get_roots() {
+            changed |= self.refresh_root(&root, force);
+        }
+        changed
+    }
+
+    /// Returns whether the source has changed
+    pub fn refresh_root(&mut self, root: &Path, force: bool) -> bool {
+        let Some(source) = self.sources.iter_mut().find(|source| source.root == root) else {
+            return false;
+        };
+
+        let mtime = get_mtime(root, source.kind);
+        // a missing directory is only reindexed if it had entries
+        let is_unchanged = source.mtime == mtime
+            && (mtime.is_some() || source.apps.is_empty() && source.hidden.is_empty());
+        if !force && is_unchanged {
+            return false;
         }
 
-        let path_indices = path::index();
+        source.mtime = mtime;
+        (source.apps, source.hidden) = match source.kind {
+            SourceKind::Desktop => desktop::index(root),
+            SourceKind::Path => (path::index(root), vec![]),
+        };
+        true
+    }
 
-        let mut final_results = HashMap::new();
+    pub fn get_roots(&self) -> Vec<(PathBuf, SourceKind)> {
+        self.sources
+            .iter()
+            .map(|source| (source.root.clone(), source.kind))
+            .collect()
+    }
 
-        for i in path_indices {
-            final_results.insert(get_exec_id(&i.exec), i);
+    pub fn save(&self) {
+        if let Err(error) = store::save(&store::get_store_path(), &self.sources) {
+            eprintln!("Couldn't save the app index: {error}");
         }
+    }
 
-        for i in desktop_indices {
-            final_results.insert(get_exec_id(&i.exec), i);
+    /// Merges the apps of the sources and the config, and deduplicates the result.
+    /// The earlier sources take precedence, even if their desktop entries are not shown,
+    /// and the binaries of the PATH are hidden by their desktop entries.
+    pub fn get_apps(&self) -> Vec<AppIndex> {
+        let (desktop_sources, path_sources): (Vec<_>, Vec<_>) = self
+            .sources
+            .iter()
+            .partition(|source| source.kind == SourceKind::Desktop);
+
+        let mut apps = Vec::new();
+        let mut desktop_ids = HashSet::new();
+        let mut exec_ids = HashSet::new();
+        for source in desktop_sources {
+            for app in &source.apps {
+                let desktop_id = app.desktop_id().unwrap_or_else(|| app.exec.clone());
+                if !desktop_ids.insert(desktop_id) {
+                    continue;
+                }
+                // sandboxed apps start through `flatpak run` or `snap run`, which are not the apps themselves,
+                // and the apps which are not listed don't replace the listed binaries
+                if app.sandbox.is_none() && !app.no_display {
+                    exec_ids.insert(get_exec_id(&app.exec));
+                }
+                apps.push(app.clone());
+            }
+            desktop_ids.extend(source.hidden.iter().filter_map(|path| get_desktop_id(path)));
+        }
+        for app in path_sources.iter().flat_map(|source| &source.apps) {
+            if exec_ids.insert(get_exec_id(&app.exec)) {
+                apps.push(app.clone());
+            }
         }
 
-        final_results.into_values().collect()
+        for entry in &self.entries {
+            let replaced = entry.replaces.as_ref().and_then(|reference| {
+                let position = apps.iter().position(|app| app.is_referred_by(reference))?;
+                Some(apps.swap_remove(position))
+            });
+            apps.push(entry.to_app_index(replaced));
+        }
+        apps
     }
 }
 
-fn get_exec_id(id: &str) -> String {
-    id.rsplit('/')
-        .next()
-        .unwrap()
-        .split(' ')
+/// The directories to index: the applications directories, the Flatpak and Snap exports,
+/// then the PATH if it's enabled
+fn get_roots(index_path: bool) -> Vec<(PathBuf, SourceKind)> {
+    let mut roots: Vec<(PathBuf, SourceKind)> = Vec::new();
+    let sandbox_roots = [get_data_home().join("flatpak/exports/share/applications")]
+        .into_iter()
+        .chain(SYSTEM_SANDBOX_ROOTS.map(PathBuf::from));
+    let desktop_roots = default_paths()
+        .into_iter()
+        .chain(sandbox_roots)
+        .map(|root| (root, SourceKind::Desktop));
+    let path_roots = std::env::var("PATH")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|root| !root.is_empty() && index_path)
+        .map(|root| (PathBuf::from(root), SourceKind::Path))
+        .collect::<Vec<_>>();
+
+    for root in desktop_roots.chain(path_roots) {
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    roots
+}
+
+/// The latest modification of the directory. Desktop directories are checked recursively with their files,
+/// because editing a file in place doesn't change the directory.
+fn get_mtime(root: &Path, kind: SourceKind) -> Option<SystemTime> {
+    let mtime = fs::metadata(root).and_then(|meta| meta.modified()).ok()?;
+    if kind == SourceKind::Path {
+        return Some(mtime);
+    }
+
+    let children = fs::read_dir(root)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.is_dir() {
+                get_mtime(&path, kind)
+            } else {
+                entry.metadata().and_then(|meta| meta.modified()).ok()
+            }
+        });
+
+    children.chain([mtime]).max()
+}
+
+/// The file name of the program, without its directory, its quotes and the arguments
+fn get_exec_id(exec: &str) -> String {
+    let program = exec::tokenize(exec)
+        .and_then(|tokens| tokens.into_iter().next())
+        .unwrap_or_default();
+    program
+        .rsplit('/')
         .next()
-        .unwrap()
+        .unwrap_or_default()
         .to_lowercase()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::prelude::PermissionsExt;
+
+    use super::*;
+
+    #[test]
+    fn test_refresh() {
+        let root = std::env::temp_dir().join(format!("vonal-test-refresh-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let executable = root.join("vonal-test-app");
+        fs::write(&executable, "").unwrap();
+        fs::set_permissions(&executable, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut indexer = Indexer {
+            sources: vec![Source {
+                root: root.clone(),
+                kind: SourceKind::Path,
+                mtime: None,
+                apps: vec![],
+                hidden: vec![],
+            }],
+            entries: vec![],
+        };
+        assert!(indexer.refresh(false));
+        assert!(!indexer.refresh(false));
+        assert!(indexer.refresh(true));
+
+        let names: Vec<_> = indexer.get_apps().into_iter().map(|app| app.name).collect();
+        assert_eq!(names, vec!["vonal-test-app"]);
+
+        fs::remove_dir_all(&root).unwrap();
+        assert!(indexer.refresh(false));
+        assert!(indexer.get_apps().is_empty());
+    }
+
+    fn entry(name: &str, exec: &str, replaces: Option<&str>) -> UserEntry {
+        UserEntry {
+            name: name.to_string(),
+            exec: exec.to_string(),
+            generic_name: None,
+            keywords: vec![],
+            icon: None,
+            terminal: false,
+            replaces: replaces.map(str::to_string),
+            actions: vec![],
+        }
+    }
+
+    #[test]
+    fn test_entries() {
+        let indexer = Indexer {
+            sources: vec![Source {
+                root: PathBuf::from("/usr/bin"),
+                kind: SourceKind::Path,
+                mtime: None,
+                apps: vec![AppIndex {
+                    name: "firefox".to_string(),
+                    exec: "/usr/bin/firefox".to_string(),
+                    ..Default::default()
+                }],
+                hidden: vec![],
+            }],
+            entries: vec![
+                entry(
+                    "Private browser",
+                    "firefox --private-window",
+                    Some("firefox"),
+                ),
+                entry("deploy-staging", "ssh staging ./deploy", None),
+            ],
+        };
+
+        let mut names: Vec<_> = indexer.get_apps().into_iter().map(|app| app.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["Private browser", "deploy-staging"]);
+    }
+
+    #[test]
+    fn test_deduplication() {
+        let app = |name: &str, exec: &str, desktop_file: Option<&str>| AppIndex {
+            name: name.to_string(),
+            exec: exec.to_string(),
+            desktop_file: desktop_file.map(PathBuf::from),
+            sandbox: desktop_file
+                .filter(|path| path.contains("/flatpak/"))
+                .map(|_| traits::Sandbox::Flatpak),
+            ..Default::default()
+        };
+        let source = |root: &str, kind: SourceKind, apps: Vec<AppIndex>| Source {
+            root: PathBuf::from(root),
+            kind,
+            mtime: None,
+            apps,
+            hidden: vec![],
+        };
+        let flatpak_root = "/var/lib/flatpak/exports/share/applications";
+        let indexer = Indexer {
+            sources: vec![
+                source(
+                    "/usr/share/applications",
+                    SourceKind::Desktop,
+                    vec![app(
+                        "Firefox",
+                        "firefox %u",
+                        Some("/usr/share/applications/firefox.desktop"),
+                    )],
+                ),
+                source(
+                    flatpak_root,
+                    SourceKind::Desktop,
+                    vec![
+                        app(
+                            "GIMP",
+                            "/usr/bin/flatpak run org.gimp.GIMP",
+                            Some(&format!("{flatpak_root}/org.gimp.GIMP.desktop")),
+                        ),
+                        app(
+                            "Inkscape",
+                            "/usr/bin/flatpak run org.inkscape.Inkscape",
+                            Some(&format!("{flatpak_root}/org.inkscape.Inkscape.desktop")),
+                        ),
+                        // the earlier directory takes precedence
+                        app(
+                            "Firefox Flatpak",
+                            "/usr/bin/flatpak run org.mozilla.firefox",
+                            Some(&format!("{flatpak_root}/firefox.desktop")),
+                        ),
+                    ],
+                ),
+                source(
+                    "/usr/bin",
+                    SourceKind::Path,
+                    vec![
+                        // the binaries of the PATH are quoted
+                        app("firefox", "\"/usr/bin/firefox\"", None),
+                        app("flatpak", "\"/usr/bin/flatpak\"", None),
+                    ],
+                ),
+            ],
+            entries: vec![],
+        };
+
+        let mut names: Vec<_> = indexer.get_apps().into_iter().map(|app| app.name).collect();
+        names.sort();
+        assert_eq!(names, vec!["Firefox", "GIMP", "Inkscape", "flatpak"]);
+    }
+
+    #[test]
+    fn test_hidden_override() {
+        let app = |name: &str, desktop_file: &str| AppIndex {
+            name: name.to_string(),
+            exec: name.to_string(),
+            desktop_file: Some(PathBuf::from(desktop_file)),
+            ..Default::default()
+        };
+        let user_root = "/home/user/.local/share/applications";
+        let system_root = "/usr/share/applications";
+        let indexer = Indexer {
+            sources: vec![
+                Source {
+                    root: PathBuf::from(user_root),
+                    kind: SourceKind::Desktop,
+                    mtime: None,
+                    apps: vec![],
+                    hidden: vec![PathBuf::from(format!("{user_root}/htop.desktop"))],
+                },
+                Source {
+                    root: PathBuf::from(system_root),
+                    kind: SourceKind::Desktop,
+                    mtime: None,
+                    apps: vec![
+                        app("htop", &format!("{system_root}/htop.desktop")),
+                        app("vim", &format!("{system_root}/vim.desktop")),
+                    ],
+                    hidden: vec![],
+                },
+            ],
+            entries: vec![],
+        };
+
+        let names: Vec<_> = indexer.get_apps().into_iter().map(|app| app.name).collect();
+        assert_eq!(names, vec!["vim"]);
+    }
+}
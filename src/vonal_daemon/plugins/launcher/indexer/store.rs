@@ -0,0 +1,94 @@
+use std::{
+    env,
+    error::Error,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Source;
+
+/// Has to be increased when the stored types change, so the old index is dropped instead of misread
+const STORE_VERSION: u32 = 8;
+
+/// The environment variables the indexing depends on, through the locale and the visibility rules
+const ENVIRONMENT_VARIABLES: [&str; 4] = ["LC_ALL", "LC_MESSAGES", "LANG", "XDG_CURRENT_DESKTOP"];
+
+#[derive(Serialize, Deserialize)]
+struct Store {
+    version: u32,
+    environment: Vec<Option<String>>,
+    sources: Vec<Source>,
+}
+
+pub fn get_store_path() -> PathBuf {
+    let cache_home = env::var("XDG_CACHE_HOME")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env::var("HOME").unwrap_or_default()).join(".cache"));
+    cache_home.join("vonal").join("launcher_index.bin")
+}
+
+/// Returns the stored sources, or nothing if they were made by a different version or environment
+pub fn load(path: &Path) -> Vec<Source> {
+    fs::read(path)
+        .ok()
+        .and_then(|bytes| bincode::deserialize::<Store>(&bytes).ok())
+        .filter(|store| store.version == STORE_VERSION && store.environment == get_environment())
+        .map(|store| store.sources)
+        .unwrap_or_default()
+}
+
+pub fn save(path: &Path, sources: &[Source]) -> Result<(), Box<dyn Error>> {
+    let store = Store {
+        version: STORE_VERSION,
+        environment: get_environment(),
+        sources: sources.to_vec(),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    // write and rename, so a concurrent load never reads a half written file
+    let temporary_path = path.with_extension("tmp");
+    fs::write(&temporary_path, bincode::serialize(&store)?)?;
+    fs::rename(temporary_path, path)?;
+    Ok(())
+}
+
+fn get_environment() -> Vec<Option<String>> {
+    ENVIRONMENT_VARIABLES
+        .iter()
+        .map(|name| env::var(name).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::launcher::indexer::SourceKind;
+
+    #[test]
+    fn test_save_and_load() {
+        let directory = env::temp_dir().join(format!("vonal-test-store-{}", std::process::id()));
+        let path = directory.join("launcher_index.bin");
+
+        let sources = vec![Source {
+            root: PathBuf::from("/usr/bin"),
+            kind: SourceKind::Path,
+            mtime: Some(std::time::SystemTime::UNIX_EPOCH),
+            apps: vec![],
+            hidden: vec![],
+        }];
+        save(&path, &sources).unwrap();
+
+        let loaded = load(&path);
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].root, sources[0].root);
+        assert_eq!(loaded[0].mtime, sources[0].mtime);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
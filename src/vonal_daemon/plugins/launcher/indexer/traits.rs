@@ -1,19 +1,116 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
-#[derive(Debug, Clone)]
+use crate::utils::search::translit;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppAction {
     pub name: String,
     pub command: String,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Sandbox {
+    Flatpak,
+    Snap,
+}
+
+impl Display for Sandbox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Sandbox::Flatpak => "Flatpak",
+            Sandbox::Snap => "Snap",
+        })
+    }
+}
+
 // TODO: remove clone
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppIndex {
     pub exec: String,
+    /// The name in the user's language
     pub name: String,
+    /// The English name, if the entry has a translation
+    pub untranslated_name: Option<String>,
+    /// The Latin spellings of the names in other scripts, like `Zhong Duan` for `终端`
+    pub transliterations: Vec<String>,
     pub generic_name: Option<String>,
+    pub comment: Option<String>,
+    pub keywords: Vec<String>,
+    pub categories: Vec<String>,
+    pub mime_types: Vec<String>,
+    pub icon: Option<String>,
+    /// The .desktop file the entry comes from, needed by the `%k` field code
+    pub desktop_file: Option<PathBuf>,
+    /// The app has to be started in a terminal
+    pub terminal: bool,
+    /// `Path=` of the desktop entry
+    pub working_directory: Option<PathBuf>,
+    /// The app ends the startup notification on its own
+    pub startup_notify: bool,
+    /// The window manager can end the startup notification by this class
+    pub startup_wm_class: Option<String>,
+    /// Flatpak and Snap apps are shown with their origin
+    pub sandbox: Option<Sandbox>,
+    /// `NoDisplay=true`: the app is not listed, but it can still open files and URLs
+    pub no_display: bool,
     pub actions: Vec<AppAction>,
 }
 
+impl AppIndex {
+    /// See [`get_desktop_id`]
+    pub fn desktop_id(&self) -> Option<String> {
+        get_desktop_id(self.desktop_file.as_ref()?)
+    }
+
+    /// The Latin spellings of the name, the English name and the generic name, computed when indexing
+    pub fn get_transliterations(&self) -> Vec<String> {
+        let mut transliterations: Vec<String> = [
+            Some(&self.name),
+            self.untranslated_name.as_ref(),
+            self.generic_name.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|name| translit::transliterate(name))
+        .collect();
+        transliterations.dedup();
+        transliterations
+    }
+
+    /// Whether the config refers to this app, by its name or by the name of its .desktop file
+    pub fn is_referred_by(&self, reference: &str) -> bool {
+        let desktop_file_name = self
+            .desktop_file
+            .as_ref()
+            .and_then(|path| path.file_name()?.to_str());
+        [Some(&self.name), self.untranslated_name.as_ref()]
+            .into_iter()
+            .flatten()
+            .any(|name| name.eq_ignore_ascii_case(reference))
+            || desktop_file_name.is_some_and(|file_name| {
+                file_name == reference || file_name.strip_suffix(".desktop") == Some(reference)
+            })
+    }
+}
+
+/// The path of the .desktop file inside the applications directory, with dashes instead of slashes,
+/// like `kde-okular.desktop` for `applications/kde/okular.desktop`
+pub fn get_desktop_id(path: &Path) -> Option<String> {
+    let components: Vec<&str> = path
+        .iter()
+        .map(|component| component.to_str())
+        .collect::<Option<_>>()?;
+    let start = components
+        .iter()
+        .rposition(|component| *component == "applications")
+        .map_or(components.len() - 1, |position| position + 1);
+    Some(components[start..].join("-"))
+}
+
 pub trait IndexApps {
     fn index(&self) -> Vec<AppIndex>;
 }
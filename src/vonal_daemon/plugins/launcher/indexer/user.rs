@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use toml::Value;
+
+use super::traits::{AppAction, AppIndex};
+use crate::config::{FromConfig, ToConfig};
+
+/// An app defined in the config by `[[launcher_plugin.entries]]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserEntry {
+    pub name: String,
+    pub exec: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generic_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub terminal: bool,
+    /// The name or the .desktop file of an indexed app, which this entry takes the place of.
+    /// The fields missing from the entry are taken from that app.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub actions: Vec<UserAction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserAction {
+    pub name: String,
+    pub exec: String,
+}
+
+impl UserEntry {
+    pub fn to_app_index(&self, replaced: Option<AppIndex>) -> AppIndex {
+        let replaced = replaced.unwrap_or_default();
+        let actions = if self.actions.is_empty() {
+            replaced.actions
+        } else {
+            self.actions
+                .iter()
+                .map(|action| AppAction {
+                    name: action.name.clone(),
+                    command: action.exec.clone(),
+                })
+                .collect()
+        };
+
+        let mut app = AppIndex {
+            exec: self.exec.clone(),
+            name: self.name.clone(),
+            // the replaced name is found too, so the renamed app is found as before
+            untranslated_name: Some(replaced.name).filter(|name| !name.is_empty()),
+            generic_name: self.generic_name.clone().or(replaced.generic_name),
+            keywords: if self.keywords.is_empty() {
+                replaced.keywords
+            } else {
+                self.keywords.clone()
+            },
+            icon: self.icon.clone().or(replaced.icon),
+            terminal: self.terminal || replaced.terminal,
+            actions,
+            // the entries of the user are listed, even if the replaced app was not
+            no_display: false,
+            ..replaced
+        };
+        app.transliterations = app.get_transliterations();
+        app
+    }
+}
+
+impl FromConfig for UserEntry {
+    fn from_config(raw: &Value) -> Option<Self> {
+        raw.clone().try_into().ok()
+    }
+
+    fn expected() -> Option<String> {
+        Some(
+            "a table with a name and an exec, and optionally a generic_name, keywords, an icon, \
+            terminal, replaces and actions (a list of tables with a name and an exec)"
+                .into(),
+        )
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        let strings = json!({ "type": "array", "items": { "type": "string" } });
+        Some(json!({
+            "type": "object",
+            "required": ["name", "exec"],
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string" },
+                "exec": { "type": "string" },
+                "generic_name": { "type": "string" },
+                "keywords": strings,
+                "icon": { "type": "string" },
+                "terminal": { "type": "boolean" },
+                "replaces": { "type": "string" },
+                "actions": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "required": ["name", "exec"],
+                        "additionalProperties": false,
+                        "properties": {
+                            "name": { "type": "string" },
+                            "exec": { "type": "string" },
+                        },
+                    },
+                },
+            },
+        }))
+    }
+}
+
+impl ToConfig for UserEntry {
+    fn to_config(self) -> Value {
+        Value::try_from(self).expect("The user entry can't be serialized")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_config() {
+        let raw: Value = toml::from_str(
+            r#"
+            name = "VPN up"
+            exec = "nmcli connection up vpn"
+            keywords = ["network"]
+            actions = [{ name = "Down", exec = "nmcli connection down vpn" }]
+            "#,
+        )
+        .unwrap();
+        let entry = UserEntry::from_config(&raw).unwrap();
+        assert_eq!(entry.actions[0].name, "Down");
+        assert_eq!(
+            UserEntry::from_config(&entry.clone().to_config()),
+            Some(entry)
+        );
+
+        let typo: Value = toml::from_str("name = \"a\"\nexec = \"b\"\nicn = \"c\"").unwrap();
+        assert_eq!(UserEntry::from_config(&typo), None);
+    }
+
+    #[test]
+    fn test_replace() {
+        let entry = UserEntry {
+            name: "Private browser".to_string(),
+            exec: "firefox --private-window %u".to_string(),
+            generic_name: None,
+            keywords: vec![],
+            icon: None,
+            terminal: false,
+            replaces: Some("firefox".to_string()),
+            actions: vec![],
+        };
+        let firefox = AppIndex {
+            exec: "firefox %u".to_string(),
+            name: "Firefox".to_string(),
+            generic_name: Some("Web Browser".to_string()),
+            keywords: vec!["internet".to_string()],
+            categories: vec!["Network".to_string()],
+            icon: Some("firefox".to_string()),
+            startup_notify: true,
+            ..Default::default()
+        };
+
+        let app = entry.to_app_index(Some(firefox));
+        assert_eq!(app.name, "Private browser");
+        assert_eq!(app.untranslated_name.as_deref(), Some("Firefox"));
+        assert_eq!(app.exec, "firefox --private-window %u");
+        assert_eq!(app.icon.as_deref(), Some("firefox"));
+        assert_eq!(app.keywords, vec!["internet"]);
+        assert!(app.startup_notify);
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use inotify::{EventMask, Inotify, WatchDescriptor, WatchMask};
+
+use super::{traits::AppIndex, Indexer, SourceKind};
+
+/// How often the events are read
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Package managers touch many files in a row, so the reindexing waits until it's quiet for this long
+const DEBOUNCE: Duration = Duration::from_secs(1);
+
+/// Keeps the index fresh on a background thread, and hands over the new apps on every change.
+/// The thread stops when this is dropped.
+pub struct IndexWatcher {
+    receiver: Receiver<Vec<AppIndex>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl IndexWatcher {
+    /// Refreshes the stale sources of the indexer (or all of them if it's forced), then watches them.
+    pub fn start(indexer: Indexer, force: bool) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let stopped = Arc::new(AtomicBool::new(false));
+        let stopped_clone = stopped.clone();
+
+        thread::spawn(move || watch(indexer, force, &sender, &stopped_clone));
+
+        Self { receiver, stopped }
+    }
+
+    /// Returns the latest apps, if there was a change since the last call
+    pub fn try_get_update(&self) -> Option<Vec<AppIndex>> {
+        self.receiver.try_iter().last()
+    }
+}
+
+impl Drop for IndexWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+fn watch(mut indexer: Indexer, force: bool, sender: &Sender<Vec<AppIndex>>, stopped: &AtomicBool) {
+    if indexer.refresh(force) {
+        if sender.send(indexer.get_apps()).is_err() {
+            return;
+        }
+        indexer.save();
+    }
+
+    let mut watches = match Watches::new(&indexer.get_roots()) {
+        Ok(watches) => watches,
+        Err(error) => {
+            eprintln!("The app index won't be updated automatically: {error}");
+            return;
+        }
+    };
+
+    let mut dirty_roots = HashSet::new();
+    let mut last_event = Instant::now();
+
+    while !stopped.load(Ordering::Relaxed) {
+        let changed_roots = watches.read_changed_roots();
+        if !changed_roots.is_empty() {
+            dirty_roots.extend(changed_roots);
+            last_event = Instant::now();
+        }
+
+        if !dirty_roots.is_empty() && last_event.elapsed() >= DEBOUNCE {
+            let mut changed = false;
+            for root in dirty_roots.drain() {
+                changed |= indexer.refresh_root(&root, false);
+            }
+            if changed {
+                if sender.send(indexer.get_apps()).is_err() {
+                    return;
+                }
+                indexer.save();
+            }
+        }
+
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+struct Watches {
+    inotify: Inotify,
+    buffer: [u8; 4096],
+    /// the watched directory, the root it belongs to, and whether its subdirectories are watched too
+    directories: HashMap<WatchDescriptor, (PathBuf, PathBuf, bool)>,
+    /// the closest existing ancestors of the missing roots, where they would be created
+    ancestors: HashSet<WatchDescriptor>,
+    /// the roots which don't exist, and whether their subdirectories would be watched
+    missing: Vec<(PathBuf, bool)>,
+}
+
+impl Watches {
+    fn new(roots: &[(PathBuf, SourceKind)]) -> std::io::Result<Self> {
+        let mut watches = Self {
+            inotify: Inotify::init()?,
+            buffer: [0; 4096],
+            directories: HashMap::new(),
+            ancestors: HashSet::new(),
+            missing: Vec::new(),
+        };
+        for (root, kind) in roots {
+            watches.add_root(root, *kind == SourceKind::Desktop);
+        }
+        Ok(watches)
+    }
+
+    /// Watches the root, or if it's missing, its closest existing ancestor until the root is created.
+    /// Returns whether the root is watched.
+    fn add_root(&mut self, root: &Path, recursive: bool) -> bool {
+        let mut watched_ancestor = None;
+        loop {
+            if root.is_dir() {
+                self.add(root, root, recursive);
+                return true;
+            }
+            // if a closer ancestor was created before it was watched, that one is watched too
+            match root.ancestors().skip(1).find(|ancestor| ancestor.is_dir()) {
+                Some(ancestor) if watched_ancestor != Some(ancestor) => {
+                    // added to the events of the ancestor, if it's watched already
+                    let mask = WatchMask::CREATE | WatchMask::MOVED_TO | WatchMask::MASK_ADD;
+                    if let Ok(descriptor) = self.inotify.watches().add(ancestor, mask) {
+                        self.ancestors.insert(descriptor);
+                    }
+                    watched_ancestor = Some(ancestor);
+                }
+                _ => {
+                    self.missing.push((root.to_path_buf(), recursive));
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn add(&mut self, directory: &Path, root: &Path, recursive: bool) {
+        let mask = WatchMask::CREATE
+            | WatchMask::DELETE
+            | WatchMask::MODIFY
+            | WatchMask::MOVED_FROM
+            | WatchMask::MOVED_TO
+            | WatchMask::ATTRIB;
+        let Ok(descriptor) = self.inotify.watches().add(directory, mask) else {
+            return;
+        };
+        self.directories.insert(
+            descriptor,
+            (directory.to_path_buf(), root.to_path_buf(), recursive),
+        );
+
+        if recursive {
+            let subdirectories = fs::read_dir(directory)
+                .into_iter()
+                .flatten()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path.is_dir());
+            for subdirectory in subdirectories {
+                self.add(&subdirectory, root, recursive);
+            }
+        }
+    }
+
+    /// Returns the roots with changes, without blocking
+    fn read_changed_roots(&mut self) -> HashSet<PathBuf> {
+        let mut changed_roots = HashSet::new();
+        let mut new_directories = Vec::new();
+        let mut removed_roots = Vec::new();
+        let mut is_ancestor_changed = false;
+
+        match self.inotify.read_events(&mut self.buffer) {
+            Ok(events) => {
+                for event in events {
+                    let is_new_directory = event.mask.contains(EventMask::ISDIR)
+                        && event
+                            .mask
+                            .intersects(EventMask::CREATE | EventMask::MOVED_TO);
+                    if self.ancestors.contains(&event.wd) {
+                        is_ancestor_changed |= is_new_directory;
+                        if event.mask.contains(EventMask::IGNORED) {
+                            self.ancestors.remove(&event.wd);
+                        }
+                    }
+
+                    let Some((directory, root, recursive)) = self.directories.get(&event.wd) else {
+                        continue;
+                    };
+                    changed_roots.insert(root.clone());
+
+                    if let (true, true, Some(name)) = (is_new_directory, *recursive, event.name) {
+                        new_directories.push((directory.join(name), root.clone()));
+                    }
+                    // the directory was removed, and if it was the root, it's waited for again
+                    if event.mask.contains(EventMask::IGNORED) {
+                        if let Some((directory, root, recursive)) =
+                            self.directories.remove(&event.wd)
+                        {
+                            if directory == root {
+                                removed_roots.push((root, recursive));
+                            }
+                        }
+                    }
+                }
+            }
+            Err(error) if error.kind() == ErrorKind::WouldBlock => {}
+            Err(error) => eprintln!("Couldn't read the changes of the app directories: {error}"),
+        }
+
+        for (directory, root) in new_directories {
+            self.add(&directory, &root, true);
+        }
+        for (root, recursive) in removed_roots {
+            self.add_root(&root, recursive);
+        }
+        if is_ancestor_changed {
+            for (root, recursive) in std::mem::take(&mut self.missing) {
+                if self.add_root(&root, recursive) {
+                    changed_roots.insert(root);
+                }
+            }
+        }
+        changed_roots
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_missing_root() {
+        let directory = env::temp_dir().join(format!("vonal-watcher-{}", std::process::id()));
+        let root = directory.join("share/applications");
+        fs::create_dir_all(&directory).unwrap();
+
+        let mut watches = Watches::new(&[(root.clone(), SourceKind::Desktop)]).unwrap();
+        assert!(watches.read_changed_roots().is_empty());
+
+        fs::create_dir_all(&root).unwrap();
+        assert_eq!(watches.read_changed_roots(), HashSet::from([root.clone()]));
+        fs::write(root.join("a.desktop"), "").unwrap();
+        assert_eq!(watches.read_changed_roots(), HashSet::from([root.clone()]));
+
+        // a removed root is waited for again
+        fs::remove_dir_all(directory.join("share")).unwrap();
+        watches.read_changed_roots();
+        fs::create_dir_all(&root).unwrap();
+        assert_eq!(watches.read_changed_roots(), HashSet::from([root]));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,232 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::json;
+use toml::Value;
+
+use super::{exec, indexer::traits::AppIndex};
+use crate::{
+    config::{FromConfig, ToConfig},
+    utils::glob,
+};
+
+/// How some apps are launched, defined in the config by `[[launcher_plugin.launch_profiles]]`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LaunchProfile {
+    pub name: String,
+    /// The names or the .desktop files of the apps which are always launched by this profile
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub apps: Vec<String>,
+    /// Globs of the commands which are always launched by this profile, like `"*steam*"`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exec: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// The command put before the command of the app, like `"firejail --net=none"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapper: Option<String>,
+    /// Read like the paths of the config, with `~` and the variables expanded
+    #[serde(
+        default,
+        deserialize_with = "deserialize_path",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub working_directory: Option<PathBuf>,
+}
+
+impl LaunchProfile {
+    pub fn matches(&self, app: &AppIndex) -> bool {
+        let desktop_id = app.desktop_id();
+        self.apps.iter().any(|reference| {
+            app.is_referred_by(reference) || desktop_id.as_deref() == Some(reference)
+        }) || self
+            .exec
+            .iter()
+            .any(|pattern| glob::matches(pattern, &app.exec))
+    }
+
+    /// Checks the wrapper when the config is loaded, instead of at a launch
+    pub fn validate(&self) -> Result<(), String> {
+        self.get_wrapper().map(|_| ())
+    }
+
+    fn get_wrapper(&self) -> Result<Vec<String>, String> {
+        let Some(wrapper) = &self.wrapper else {
+            return Ok(vec![]);
+        };
+        exec::tokenize(wrapper).ok_or_else(|| {
+            format!(
+                "The wrapper of the {} launch profile is invalid: {wrapper}",
+                self.name
+            )
+        })
+    }
+}
+
+fn deserialize_path<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<PathBuf>, D::Error> {
+    let raw = Value::deserialize(deserializer)?;
+    PathBuf::from_config(&raw).map(Some).ok_or_else(|| {
+        serde::de::Error::custom(format!(
+            "Expected {}",
+            PathBuf::expected().unwrap_or_default()
+        ))
+    })
+}
+
+/// The options of the profiles of a launch together
+#[derive(Debug, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub env: Vec<(String, String)>,
+    pub wrapper: Vec<String>,
+    pub working_directory: Option<PathBuf>,
+}
+
+/// The later profiles override the variables and the working directory of the earlier ones,
+/// and their wrappers are put inside the wrappers of the earlier ones.
+pub fn get_options(profiles: &[&LaunchProfile]) -> Result<LaunchOptions, String> {
+    let mut options = LaunchOptions::default();
+    let mut env = BTreeMap::new();
+    for profile in profiles {
+        env.extend(profile.env.clone());
+        options.wrapper.extend(profile.get_wrapper()?);
+        if let Some(working_directory) = &profile.working_directory {
+            options.working_directory = Some(working_directory.clone());
+        }
+    }
+    options.env = env.into_iter().collect();
+    Ok(options)
+}
+
+impl FromConfig for LaunchProfile {
+    fn from_config(raw: &Value) -> Option<Self> {
+        raw.clone().try_into().ok()
+    }
+
+    fn expected() -> Option<String> {
+        Some(
+            "a table with a name, and optionally apps, exec (a list of globs), env (a table of \
+            variables), a wrapper and a working_directory"
+                .into(),
+        )
+    }
+
+    fn schema() -> Option<serde_json::Value> {
+        let strings = json!({ "type": "array", "items": { "type": "string" } });
+        Some(json!({
+            "type": "object",
+            "required": ["name"],
+            "additionalProperties": false,
+            "properties": {
+                "name": { "type": "string" },
+                "apps": strings,
+                "exec": strings,
+                "env": { "type": "object", "additionalProperties": { "type": "string" } },
+                "wrapper": { "type": "string" },
+                "working_directory": PathBuf::schema(),
+            },
+        }))
+    }
+}
+
+impl ToConfig for LaunchProfile {
+    fn to_config(self) -> Value {
+        Value::try_from(self).expect("The launch profile can't be serialized")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> LaunchProfile {
+        LaunchProfile {
+            name: name.to_string(),
+            apps: vec![],
+            exec: vec![],
+            env: BTreeMap::new(),
+            wrapper: None,
+            working_directory: None,
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let app = AppIndex {
+            name: "Steam".to_string(),
+            exec: "/usr/bin/steam-runtime %U".to_string(),
+            desktop_file: Some(PathBuf::from("/usr/share/applications/valve/steam.desktop")),
+            ..Default::default()
+        };
+
+        let by_apps = |apps: &[&str]| LaunchProfile {
+            apps: apps.iter().map(|app| app.to_string()).collect(),
+            ..profile("gpu")
+        };
+        assert!(by_apps(&["steam.desktop"]).matches(&app));
+        assert!(by_apps(&["valve-steam.desktop"]).matches(&app));
+        assert!(by_apps(&["steam"]).matches(&app));
+        assert!(!by_apps(&["firefox"]).matches(&app));
+
+        let by_exec = |pattern: &str| LaunchProfile {
+            exec: vec![pattern.to_string()],
+            ..profile("gpu")
+        };
+        assert!(by_exec("*steam*").matches(&app));
+        assert!(!by_exec("steam").matches(&app));
+        assert!(!profile("gpu").matches(&app));
+    }
+
+    #[test]
+    fn test_options() {
+        let sandbox = LaunchProfile {
+            env: BTreeMap::from([("A".to_string(), "1".to_string())]),
+            wrapper: Some("firejail --net=none".to_string()),
+            working_directory: Some(PathBuf::from("/tmp")),
+            ..profile("sandbox")
+        };
+        let gpu = LaunchProfile {
+            env: BTreeMap::from([
+                ("A".to_string(), "2".to_string()),
+                ("DRI_PRIME".to_string(), "1".to_string()),
+            ]),
+            wrapper: Some("'gamemoderun'".to_string()),
+            ..profile("gpu")
+        };
+
+        let options = get_options(&[&sandbox, &gpu]).unwrap();
+        assert_eq!(
+            options.env,
+            [("A", "2"), ("DRI_PRIME", "1")].map(|(k, v)| (k.to_string(), v.to_string()))
+        );
+        assert_eq!(options.wrapper, ["firejail", "--net=none", "gamemoderun"]);
+        assert_eq!(options.working_directory, Some(PathBuf::from("/tmp")));
+        assert_eq!(get_options(&[]).unwrap(), LaunchOptions::default());
+
+        let invalid = LaunchProfile {
+            wrapper: Some("firejail \"".to_string()),
+            ..profile("invalid")
+        };
+        assert!(invalid.validate().is_err());
+        assert!(get_options(&[&invalid]).is_err());
+
+        assert!(sandbox.validate().is_ok());
+    }
+
+    #[test]
+    fn test_working_directory() {
+        let parse = |working_directory: &str| {
+            let raw: Value = toml::from_str(&format!(
+                "name = \"a\"\nworking_directory = \"{working_directory}\""
+            ))
+            .unwrap();
+            LaunchProfile::from_config(&raw).map(|profile| profile.working_directory)
+        };
+        let home = PathBuf::from(std::env::var("HOME").unwrap());
+        assert_eq!(parse("~/Downloads"), Some(Some(home.join("Downloads"))));
+        assert_eq!(parse("/tmp"), Some(Some(PathBuf::from("/tmp"))));
+        assert_eq!(parse("$VONAL_SURELY_UNSET/a"), None);
+    }
+}
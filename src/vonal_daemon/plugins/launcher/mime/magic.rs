@@ -0,0 +1,214 @@
+//! The `magic` file of shared-mime-info, which recognizes the types by the content of the files
+
+const HEADER: &[u8] = b"MIME-Magic\0\n";
+
+pub struct Rule {
+    pub priority: u32,
+    pub mime_type: String,
+    matchlets: Vec<Matchlet>,
+}
+
+/// A value at an offset. It's nested into the previous matchlet with a lower indent.
+struct Matchlet {
+    indent: usize,
+    start_offset: usize,
+    value: Vec<u8>,
+    mask: Option<Vec<u8>>,
+    /// the number of offsets the value is looked for from the start offset
+    range_length: usize,
+}
+
+impl Rule {
+    /// A top level matchlet has to match, and one of its children if it has any, and so on
+    pub fn matches(&self, content: &[u8]) -> bool {
+        matches_level(&self.matchlets, 0, content)
+    }
+}
+
+/// Whether a matchlet of the level matches together with its children.
+/// The matchlets are the siblings of one level and their descendants.
+fn matches_level(matchlets: &[Matchlet], indent: usize, content: &[u8]) -> bool {
+    let starts = matchlets
+        .iter()
+        .enumerate()
+        .filter(|(_, matchlet)| matchlet.indent == indent)
+        .map(|(i, _)| i);
+
+    starts.clone().any(|start| {
+        let end = matchlets[start + 1..]
+            .iter()
+            .position(|matchlet| matchlet.indent <= indent)
+            .map_or(matchlets.len(), |position| start + 1 + position);
+        let children = &matchlets[start + 1..end];
+
+        matchlets[start].matches(content)
+            && (children.is_empty() || matches_level(children, indent + 1, content))
+    })
+}
+
+impl Matchlet {
+    fn matches(&self, content: &[u8]) -> bool {
+        (self.start_offset..self.start_offset + self.range_length).any(|offset| {
+            let Some(bytes) = content.get(offset..offset + self.value.len()) else {
+                return false;
+            };
+            match &self.mask {
+                Some(mask) => bytes
+                    .iter()
+                    .zip(&self.value)
+                    .zip(mask)
+                    .all(|((byte, value), mask)| byte & mask == value & mask),
+                None => bytes == self.value,
+            }
+        })
+    }
+}
+
+/// Returns `None` if the file is malformed
+pub fn parse(bytes: &[u8]) -> Option<Vec<Rule>> {
+    let mut parser = Parser {
+        bytes: bytes.strip_prefix(HEADER)?,
+    };
+    let mut rules = Vec::new();
+
+    while !parser.bytes.is_empty() {
+        // [priority:mime/type]
+        parser.expect(b'[')?;
+        let priority = parser.number()?;
+        parser.expect(b':')?;
+        let mime_type = parser.until(b']')?;
+        parser.expect(b'\n')?;
+
+        let mut matchlets = Vec::new();
+        while parser.bytes.first().is_some_and(|byte| *byte != b'[') {
+            matchlets.push(parser.matchlet()?);
+        }
+        rules.push(Rule {
+            priority: priority as u32,
+            mime_type: String::from_utf8_lossy(mime_type).into_owned(),
+            matchlets,
+        });
+    }
+
+    Some(rules)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Parser<'a> {
+    /// `[indent]>start-offset=value-length value[&mask][~word-size][+range-length]\n`
+    fn matchlet(&mut self) -> Option<Matchlet> {
+        let indent = if self.bytes.first() == Some(&b'>') {
+            0
+        } else {
+            self.number()?
+        };
+        self.expect(b'>')?;
+        let start_offset = self.number()?;
+        self.expect(b'=')?;
+        let length = u16::from_be_bytes([self.byte()?, self.byte()?]) as usize;
+        let mut value = self.take(length)?.to_vec();
+        let mut mask = if self.bytes.first() == Some(&b'&') {
+            self.expect(b'&')?;
+            Some(self.take(length)?.to_vec())
+        } else {
+            None
+        };
+        let word_size = if self.bytes.first() == Some(&b'~') {
+            self.expect(b'~')?;
+            self.number()?
+        } else {
+            1
+        };
+        let range_length = if self.bytes.first() == Some(&b'+') {
+            self.expect(b'+')?;
+            self.number()?
+        } else {
+            1
+        };
+        // unknown extensions are skipped until the end of the line
+        self.until(b'\n')?;
+
+        // the values of bigger words are stored in big endian
+        if cfg!(target_endian = "little") && word_size > 1 {
+            swap_words(&mut value, word_size);
+            if let Some(mask) = mask.as_mut() {
+                swap_words(mask, word_size);
+            }
+        }
+
+        Some(Matchlet {
+            indent,
+            start_offset,
+            value,
+            mask,
+            range_length: range_length.max(1),
+        })
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        let (byte, rest) = self.bytes.split_first()?;
+        self.bytes = rest;
+        Some(*byte)
+    }
+
+    fn take(&mut self, length: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < length {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(length);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn expect(&mut self, expected: u8) -> Option<()> {
+        (self.byte()? == expected).then_some(())
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let length = self
+            .bytes
+            .iter()
+            .position(|byte| !byte.is_ascii_digit())
+            .unwrap_or(self.bytes.len());
+        std::str::from_utf8(self.take(length)?).ok()?.parse().ok()
+    }
+
+    /// Returns the bytes before the delimiter, and skips the delimiter
+    fn until(&mut self, delimiter: u8) -> Option<&'a [u8]> {
+        let length = self.bytes.iter().position(|byte| *byte == delimiter)?;
+        let taken = self.take(length)?;
+        self.bytes = &self.bytes[1..];
+        Some(taken)
+    }
+}
+
+fn swap_words(bytes: &mut [u8], word_size: usize) {
+    for word in bytes.chunks_mut(word_size) {
+        word.reverse();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_matchlets() {
+        // a RIFF container is only WebP if it says so at the 8th byte
+        let mut bytes = HEADER.to_vec();
+        bytes.extend(b"[60:image/webp]\n>0=\0\x04RIFF\n1>8=\0\x04WEBP\n");
+        bytes.extend(b"[50:text/x-masked]\n>2=\0\x02ab&\0\xdf+3\n");
+        let rules = parse(&bytes).unwrap();
+
+        assert_eq!(rules.len(), 2);
+        assert!(rules[0].matches(b"RIFF\0\0\0\0WEBPVP8"));
+        assert!(!rules[0].matches(b"RIFF\0\0\0\0WAVEfmt"));
+        // the mask ignores the case of the second letter, within the range of 3 offsets
+        assert!(rules[1].matches(b"xxxxaB"));
+        assert!(!rules[1].matches(b"xxxxxaB"));
+        assert!(parse(b"not magic").is_none());
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use super::xdg::get_data_directories;
+use crate::utils::glob;
+
+mod magic;
+
+/// The beginning of the files which is checked by the magic rules
+const MAX_SNIFFED_BYTES: u64 = 65536;
+
+const DIRECTORY_TYPE: &str = "inode/directory";
+const TEXT_TYPE: &str = "text/plain";
+const BINARY_TYPE: &str = "application/octet-stream";
+
+/// The shared-mime-info database, which tells the MIME type of files by their name and their content
+#[derive(Default)]
+pub struct Database {
+    /// the most important first
+    globs: Vec<Glob>,
+    magic: Vec<magic::Rule>,
+    /// child -> parents
+    subclasses: HashMap<String, Vec<String>>,
+    /// alias -> canonical name
+    aliases: HashMap<String, String>,
+}
+
+struct Glob {
+    weight: u32,
+    pattern: String,
+    mime_type: String,
+    case_sensitive: bool,
+}
+
+impl Database {
+    /// Loads the database from the `mime` directory of every XDG data directory
+    pub fn load() -> Self {
+        let directories: Vec<_> = get_data_directories()
+            .into_iter()
+            .map(|directory| directory.join("mime"))
+            .collect();
+        Self::load_from(&directories)
+    }
+
+    fn load_from(directories: &[PathBuf]) -> Self {
+        let mut database = Self::default();
+
+        for directory in directories {
+            let read = |name: &str| fs::read_to_string(directory.join(name)).unwrap_or_default();
+            database.add_globs(&read("globs2"));
+            database.add_pairs(&read("subclasses"), true);
+            database.add_pairs(&read("aliases"), false);
+            if let Ok(bytes) = fs::read(directory.join("magic")) {
+                database
+                    .magic
+                    .extend(magic::parse(&bytes).unwrap_or_default());
+            }
+        }
+
+        // a stable sort, so the earlier directories keep their precedence
+        database.globs.sort_by(|a, b| {
+            b.weight
+                .cmp(&a.weight)
+                .then(b.pattern.len().cmp(&a.pattern.len()))
+        });
+        database.magic.sort_by_key(|rule| Reverse(rule.priority));
+        database
+    }
+
+    /// Lines of `weight:type:glob[:flags]`
+    fn add_globs(&mut self, content: &str) {
+        for line in content.lines().filter(|line| !line.starts_with('#')) {
+            let mut fields = line.splitn(4, ':');
+            let (Some(weight), Some(mime_type), Some(pattern)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+            let Ok(weight) = weight.parse() else {
+                continue;
+            };
+            let case_sensitive = fields
+                .next()
+                .is_some_and(|flags| flags.split(',').any(|flag| flag == "cs"));
+
+            self.globs.push(Glob {
+                weight,
+                pattern: if case_sensitive {
+                    pattern.to_string()
+                } else {
+                    pattern.to_lowercase()
+                },
+                mime_type: mime_type.to_string(),
+                case_sensitive,
+            });
+        }
+    }
+
+    /// Lines of two types separated by a space
+    fn add_pairs(&mut self, content: &str, are_subclasses: bool) {
+        let pairs = content
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(a, b)| (a.to_string(), b.trim().to_string()));
+        for (a, b) in pairs {
+            if are_subclasses {
+                self.subclasses.entry(a).or_default().push(b);
+            } else {
+                self.aliases.entry(a).or_insert(b);
+            }
+        }
+    }
+
+    /// The type of a file, by its name, or by its content if the name is not enough
+    pub fn get_mime_type(&self, path: &Path) -> String {
+        if path.is_dir() {
+            return DIRECTORY_TYPE.to_string();
+        }
+
+        let glob_types = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map(|name| self.match_globs(name))
+            .unwrap_or_default();
+        if glob_types.len() == 1 {
+            return self.unalias(&glob_types[0]);
+        }
+
+        let content = read_beginning(path).unwrap_or_default();
+        let magic_type = self
+            .magic
+            .iter()
+            .find(|rule| rule.matches(&content))
+            .map(|rule| rule.mime_type.as_str());
+        // the content decides between the types of the name
+        let mime_type = match (magic_type, glob_types.first()) {
+            (Some(magic_type), Some(_)) if glob_types.iter().any(|t| t == magic_type) => magic_type,
+            (_, Some(glob_type)) => glob_type,
+            (Some(magic_type), None) => magic_type,
+            (None, None) if is_text(&content) => TEXT_TYPE,
+            (None, None) => BINARY_TYPE,
+        };
+        self.unalias(mime_type)
+    }
+
+    /// The types of the globs with the highest weight and the longest pattern matching the name
+    fn match_globs(&self, name: &str) -> Vec<String> {
+        let lowercase_name = name.to_lowercase();
+        let mut matches = self.globs.iter().filter(|glob| {
+            let name = if glob.case_sensitive {
+                name
+            } else {
+                &lowercase_name
+            };
+            glob::matches(&glob.pattern, name)
+        });
+
+        let Some(best) = matches.next() else {
+            return vec![];
+        };
+        let mut mime_types = vec![best.mime_type.clone()];
+        for glob in matches {
+            if glob.weight != best.weight || glob.pattern.len() != best.pattern.len() {
+                break;
+            }
+            if !mime_types.contains(&glob.mime_type) {
+                mime_types.push(glob.mime_type.clone());
+            }
+        }
+        mime_types
+    }
+
+    fn unalias(&self, mime_type: &str) -> String {
+        self.aliases
+            .get(mime_type)
+            .cloned()
+            .unwrap_or_else(|| mime_type.to_string())
+    }
+
+    /// The type, then the types it's a kind of, closest first. E.g. `text/x-python` is a `text/plain` too.
+    pub fn get_broader_types(&self, mime_type: &str) -> Vec<String> {
+        let mut types = vec![self.unalias(mime_type)];
+        let mut visited: HashSet<String> = types.iter().cloned().collect();
+
+        let mut i = 0;
+        while let Some(current) = types.get(i).cloned() {
+            let mut parents = self.subclasses.get(&current).cloned().unwrap_or_default();
+            if current.starts_with("text/") {
+                parents.push(TEXT_TYPE.to_string());
+            }
+            for parent in parents {
+                let parent = self.unalias(&parent);
+                if visited.insert(parent.clone()) {
+                    types.push(parent);
+                }
+            }
+            i += 1;
+        }
+        types
+    }
+}
+
+fn read_beginning(path: &Path) -> Option<Vec<u8>> {
+    let mut content = Vec::new();
+    File::open(path)
+        .ok()?
+        .take(MAX_SNIFFED_BYTES)
+        .read_to_end(&mut content)
+        .ok()?;
+    Some(content)
+}
+
+/// Text has no control characters besides the whitespaces, like the shared-mime-info fallback checks
+fn is_text(content: &[u8]) -> bool {
+    content
+        .iter()
+        .take(128)
+        .all(|byte| *byte >= 0x20 || b"\n\r\t\x0c\x1b".contains(byte))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_mime_type() {
+        let directory =
+            std::env::temp_dir().join(format!("vonal-test-mime-{}", std::process::id()));
+        let mime_directory = directory.join("mime");
+        fs::create_dir_all(&mime_directory).unwrap();
+        fs::write(
+            mime_directory.join("globs2"),
+            "# comment\n50:application/pdf:*.pdf\n50:text/x-python:*.py\n10:text/x-readme:readme*\n",
+        )
+        .unwrap();
+        fs::write(
+            mime_directory.join("subclasses"),
+            "text/x-python text/x-script\n",
+        )
+        .unwrap();
+        fs::write(
+            mime_directory.join("aliases"),
+            "application/x-pdf application/pdf\n",
+        )
+        .unwrap();
+        let mut magic = b"MIME-Magic\0\n[50:application/x-pdf]\n>0=\0\x05%PDF-\n".to_vec();
+        magic.extend(b"[50:image/png]\n>0=\0\x04\x89PNG\n");
+        fs::write(mime_directory.join("magic"), magic).unwrap();
+
+        let database = Database::load_from(&[mime_directory]);
+        let file = |name: &str, content: &[u8]| {
+            let path = directory.join(name);
+            fs::write(&path, content).unwrap();
+            database.get_mime_type(&path)
+        };
+
+        assert_eq!(file("a.PDF", b""), "application/pdf");
+        assert_eq!(file("README.md", b"# readme"), "text/x-readme");
+        assert_eq!(file("document", b"%PDF-1.7"), "application/pdf");
+        assert_eq!(file("picture", b"\x89PNG\r\n"), "image/png");
+        assert_eq!(file("notes", b"hello"), "text/plain");
+        assert_eq!(file("data", b"\0\x01\x02"), "application/octet-stream");
+        assert_eq!(database.get_mime_type(&directory), "inode/directory");
+        assert_eq!(
+            database.get_broader_types("text/x-python"),
+            vec!["text/x-python", "text/x-script", "text/plain"]
+        );
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
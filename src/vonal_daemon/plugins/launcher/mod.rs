@@ -1,27 +1,143 @@
-use egui::{self, Ui};
-use regex::Regex;
-use std::process::Command;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use egui::{self, Key, KeyboardShortcut, Modifiers, Ui};
 
 use crate::{
-    config::{ConfigBuilder, ConfigError},
-    theme::list::{List, ListState},
+    config::{config_enum, ConfigBuilder, ConfigError},
+    theme::list::{List, ListState, RowUi},
+    utils::search::query::{self, Query},
 };
 
-use self::{cache::Cached, indexer::traits::AppIndex};
+use self::{
+    cache::Cached,
+    complete::Completion,
+    history::History,
+    indexer::{
+        mimeapps::MimeApps, traits::AppIndex, user::UserEntry, watcher::IndexWatcher, Indexer,
+    },
+    launch_profile::LaunchProfile,
+    open::Target,
+    placement::Placement,
+};
 
 use super::{Plugin, PluginContext};
 
 mod cache;
+mod complete;
+mod exec;
 mod finder;
+mod history;
 mod indexer;
+mod launch_profile;
+mod mime;
+mod open;
+mod placement;
+mod spawn;
+mod startup;
+mod xdg;
+
+/// The number of completions listed for the arguments of the first app
+const MAX_COMPLETIONS: usize = 5;
+
+/// The shortcuts moving the selected pinned app in the empty query view
+struct PinShortcuts {
+    up: KeyboardShortcut,
+    down: KeyboardShortcut,
+}
+
+impl Default for PinShortcuts {
+    fn default() -> Self {
+        Self {
+            up: KeyboardShortcut::new(Modifiers::ALT, Key::ArrowUp),
+            down: KeyboardShortcut::new(Modifiers::ALT, Key::ArrowDown),
+        }
+    }
+}
+
+config_enum! {
+    /// What the empty query lists
+    #[derive(Default)]
+    enum EmptyQuery {
+        #[default]
+        PinnedAndRecent => "pinned_and_recent",
+        Pinned => "pinned",
+        Nothing => "nothing",
+    }
+}
+
+/// An app which has exited unsuccessfully right after its launch
+struct LaunchFailure {
+    name: String,
+    message: String,
+    startup_id: Option<String>,
+}
+
+/// How the app is launched besides its command, by the choice of the user
+#[derive(Debug, Clone, Default)]
+pub struct LaunchChoice {
+    /// the workspace or the monitor its windows are moved to
+    pub placement: Option<Placement>,
+    /// the name of the launch profile used instead of the ones matching the app
+    pub profile: Option<String>,
+}
+
+#[derive(Clone)]
+struct PickerRow {
+    kind: &'static str,
+    name: String,
+    choice: LaunchChoice,
+}
+
+/// An app launched by Shift or by "Launch with…", waiting for a choice
+#[derive(Clone)]
+struct LaunchPicker {
+    app: AppIndex,
+    command: String,
+    args: String,
+    /// the picker is closed when the query changes
+    query: String,
+    rows: Vec<PickerRow>,
+}
 
 #[derive(Default)]
 pub struct Launcher {
     finder: finder::Finder,
-    results: Cached<Vec<AppIndex>>,
+    index_watcher: Option<IndexWatcher>,
+    /// the found apps of the query, so an unchanged query is not searched for every frame
+    found_apps: Cached<Vec<(AppIndex, Vec<usize>)>>,
+    /// the apps which can open the typed file or URL, the default one first
+    handlers: Cached<Vec<AppIndex>>,
+    /// the completions of the arguments of the first found app
+    completions: Cached<Vec<Completion>>,
+    /// loaded when a file is typed first
+    mime_database: Option<mime::Database>,
+    /// filled by the threads watching the launched apps
+    failures: Arc<Mutex<Vec<LaunchFailure>>>,
+    /// opened while the rows are rendered, which only borrow the launcher
+    picker: RefCell<Option<LaunchPicker>>,
+    /// the apps shown for the empty query
+    history: RefCell<History>,
     config_prefix: String,
     config_index_path: bool,
     config_number_of_results: usize,
+    config_terminal_command: String,
+    config_log_output: bool,
+    config_use_systemd_run: bool,
+    config_remember_launches: bool,
+    config_remember_args: bool,
+    config_empty_query: EmptyQuery,
+    config_pin_shortcuts: PinShortcuts,
+    config_window_timeout: Duration,
+    config_entries: Vec<UserEntry>,
+    config_launch_profiles: Vec<LaunchProfile>,
+    config_preferences: finder::Preferences,
 }
 
 impl Launcher {
@@ -29,50 +145,450 @@ impl Launcher {
         Default::default()
     }
 
-    pub fn reindex_apps(&mut self) {
-        self.finder = self.index_apps_and_get_finder()
+    /// Loads the saved index, then refreshes it in the background.
+    /// If it's forced, every app is reindexed, otherwise only the modified directories.
+    pub fn reindex_apps(&mut self, force: bool) {
+        let indexer = Indexer::load(self.config_index_path, self.config_entries.clone());
+        self.set_apps(indexer.get_apps());
+        self.index_watcher = Some(IndexWatcher::start(indexer, force));
+    }
+
+    fn set_apps(&mut self, apps: Vec<AppIndex>) {
+        // the pins are preferred in the order they are arranged in the empty query view
+        let preferences = finder::Preferences {
+            pinned: self.get_pinned(),
+            ..self.config_preferences.clone()
+        };
+        self.finder = finder::Finder::new(apps, &preferences);
+        self.found_apps = Default::default();
+        self.handlers = Default::default();
+    }
+
+    fn get_pinned(&self) -> Vec<String> {
+        self.history
+            .borrow()
+            .order_pinned(&self.config_preferences.pinned)
+    }
+
+    /// Moves the pinned app up or down among the found pinned apps
+    fn move_pinned(&mut self, pinned_apps: &[usize], position: usize, up: bool) {
+        let other = match up {
+            true => position.checked_sub(1),
+            false => Some(position + 1),
+        };
+        let Some(&other) = other.and_then(|other| pinned_apps.get(other)) else {
+            return;
+        };
+
+        let history = self.history.get_mut();
+        history.swap_pinned(
+            &self.config_preferences.pinned,
+            pinned_apps[position],
+            other,
+        );
+        if self.config_remember_launches {
+            history.save();
+        }
+        let apps = self.finder.get_apps().into_iter().cloned().collect();
+        self.set_apps(apps);
+    }
+
+    /// Starts the command of the app or one of its actions, without a shell.
+    /// The launch profiles matching the app, or the chosen one, set its environment and wrap its command.
+    pub fn run(
+        &self,
+        ctx: &PluginContext,
+        app: &AppIndex,
+        command: &str,
+        args: &str,
+        choice: &LaunchChoice,
+    ) -> Result<(), String> {
+        let profiles: Vec<&LaunchProfile> = self
+            .config_launch_profiles
+            .iter()
+            .filter(|profile| match &choice.profile {
+                Some(name) => profile.name == *name,
+                None => profile.matches(app),
+            })
+            .collect();
+        let profile_options = launch_profile::get_options(&profiles)?;
+        let placement = choice.placement;
+        let log_name = app
+            .desktop_file
+            .as_deref()
+            .and_then(|path| path.file_stem()?.to_str())
+            .unwrap_or(&app.name);
+        let args = exec::split_args(args)
+            .ok_or_else(|| format!("A quote is not closed in the arguments: {args}"))?;
+        let commands = exec::build_commands(command, &args, app)
+            .ok_or_else(|| format!("The command of {} is invalid: {command}", app.name))?;
+
+        for mut argv in commands {
+            if app.terminal {
+                argv = exec::wrap_in_terminal(&self.config_terminal_command, argv).ok_or_else(
+                    || {
+                        format!(
+                            "The terminal command is invalid: {}",
+                            self.config_terminal_command
+                        )
+                    },
+                )?;
+            }
+            if !profile_options.wrapper.is_empty() {
+                argv = profile_options
+                    .wrapper
+                    .iter()
+                    .cloned()
+                    .chain(argv)
+                    .collect();
+            }
+
+            let startup_id = startup::is_supported(app).then(|| startup::new_startup_id(app));
+            if let Some(startup_id) = &startup_id {
+                let screen = ctx.gl_window.get_screen_number();
+                let desktop = placement.and_then(|placement| placement.get_desktop());
+                ctx.gl_window.send_startup_info(&startup::new_message(
+                    startup_id, app, &argv[0], screen, desktop,
+                ));
+            }
+
+            let options = spawn::SpawnOptions {
+                working_directory: profile_options
+                    .working_directory
+                    .as_deref()
+                    .or(app.working_directory.as_deref()),
+                log_name: self.config_log_output.then_some(log_name),
+                use_systemd_run: self.config_use_systemd_run,
+                startup_id: startup_id.as_deref(),
+                env: &profile_options.env,
+            };
+            let failures = self.failures.clone();
+            let egui_ctx = ctx.egui_ctx.clone();
+            let name = app.name.clone();
+            let failure_startup_id = startup_id.clone();
+            let on_failure = move |message| {
+                failures.lock().unwrap().push(LaunchFailure {
+                    name,
+                    message,
+                    startup_id: failure_startup_id,
+                });
+                egui_ctx.request_repaint();
+            };
+
+            match spawn::spawn(&argv, &options, on_failure) {
+                Ok(pid) => {
+                    if let Some(placement) = placement {
+                        placement::place_windows(
+                            pid,
+                            startup_id,
+                            placement,
+                            self.config_window_timeout,
+                        );
+                    }
+                }
+                Err(error) => {
+                    if let Some(startup_id) = &startup_id {
+                        ctx.gl_window
+                            .send_startup_info(&startup::remove_message(startup_id));
+                    }
+                    return Err(format!("Couldn't start {}: {error}", app.name));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the command, then hides the window, or shows why it couldn't start.
+    /// With Shift, the workspace or the monitor of the app is picked first.
+    fn launch(
+        &self,
+        row_ui: &mut RowUi,
+        ctx: &mut PluginContext,
+        app: &AppIndex,
+        command: &str,
+        args: &str,
+    ) {
+        if row_ui.ui.input(|i| i.modifiers.shift) {
+            let rows: Vec<PickerRow> = placement::get_targets(ctx.gl_window)
+                .into_iter()
+                .map(|target| PickerRow {
+                    kind: target.kind,
+                    name: target.name,
+                    choice: LaunchChoice {
+                        placement: Some(target.placement),
+                        profile: None,
+                    },
+                })
+                .collect();
+            if !rows.is_empty() {
+                self.open_picker(row_ui, ctx, app, command, args, rows);
+                return;
+            }
+        }
+        self.launch_with(row_ui, ctx, app, command, args, &LaunchChoice::default());
     }
 
-    pub fn run(&self, command: &str, args: &str) -> Option<()> {
-        let switches_regex = Regex::new(r"%(f|F|u|U|i|c|k|v|m|d|D|n|N)").unwrap();
-        let mut new_command = switches_regex.replace_all(command, args).to_string();
-        if new_command == command {
-            new_command = format!("{command} {args}");
+    fn launch_with(
+        &self,
+        row_ui: &mut RowUi,
+        ctx: &mut PluginContext,
+        app: &AppIndex,
+        command: &str,
+        args: &str,
+        choice: &LaunchChoice,
+    ) {
+        match self.run(ctx, app, command, args, choice) {
+            Ok(()) => {
+                if self.config_remember_launches {
+                    let mut history = self.history.borrow_mut();
+                    history.record(app, SystemTime::now());
+                    if self.config_remember_args {
+                        history.record_args(app, args);
+                    }
+                    history.save();
+                }
+                ctx.query.clear();
+                ctx.gl_window.window().set_visible(false);
+                ListState::reset(row_ui.ui.ctx(), 0);
+            }
+            Err(error) => ctx.set_error(error),
         }
-        Command::new("/bin/sh")
-            .arg("-c")
-            .arg(new_command)
-            .spawn()
-            .ok()?;
-        Some(())
     }
 
-    fn index_apps_and_get_finder(&self) -> finder::Finder {
-        let indices = indexer::Indexer::default().index(self.config_index_path);
-        finder::Finder::new(indices)
+    /// The found apps with the matched graphemes of their names
+    fn find_apps(&mut self, query: &str) -> Vec<(AppIndex, Vec<usize>)> {
+        let Self {
+            found_apps,
+            finder,
+            config_number_of_results,
+            ..
+        } = self;
+        found_apps.get_or_create(query.to_string(), || {
+            finder
+                .find(query, *config_number_of_results)
+                .into_iter()
+                .map(|app_match| (app_match.index.clone(), app_match.positions))
+                .collect()
+        })
     }
 
-    fn find_apps(&mut self, query: &str) -> Vec<AppIndex> {
-        self.results.get_or_create(query.to_string(), || {
-            self.finder
-                .find(query, self.config_number_of_results)
+    /// The handlers are looked up among every app, because the ones which are not listed can open files too
+    fn find_handlers(&mut self, target: &Target) -> Vec<AppIndex> {
+        let Self {
+            handlers,
+            mime_database,
+            finder,
+            ..
+        } = self;
+        handlers.get_or_create(target.to_argument(), || {
+            let database = mime_database.get_or_insert_with(mime::Database::load);
+            MimeApps::load()
+                .get_handlers(&target.get_mime_types(database), &finder.get_apps())
                 .into_iter()
-                .map(|app_match| app_match.index)
                 .cloned()
-                .collect::<Vec<AppIndex>>()
+                .collect()
         })
     }
 
-    fn split_query(query: &str) -> (String, String) {
-        let query_sections = query.split(' ').collect::<Vec<_>>();
+    /// Lists the choices instead of the results, until one is picked or the query changes
+    fn open_picker(
+        &self,
+        row_ui: &mut RowUi,
+        ctx: &PluginContext,
+        app: &AppIndex,
+        command: &str,
+        args: &str,
+        rows: Vec<PickerRow>,
+    ) {
+        *self.picker.borrow_mut() = Some(LaunchPicker {
+            app: app.clone(),
+            command: command.to_string(),
+            args: args.to_string(),
+            query: ctx.query.clone(),
+            rows,
+        });
+        ListState::reset(row_ui.ui.ctx(), 0);
+    }
 
-        if let Some((keyword, args)) = query_sections.split_first() {
-            let keyword = keyword.to_string();
-            let args = args.join(" ");
-            return (keyword, args);
+    /// Renders the picker instead of the results, until the query changes
+    fn render_open_picker(&mut self, ui: &mut Ui, ctx: &mut PluginContext) -> bool {
+        let picker = self.picker.get_mut();
+        if picker
+            .as_ref()
+            .is_some_and(|picker| picker.query != *ctx.query)
+        {
+            *picker = None;
         }
+        let Some(picker) = picker.clone() else {
+            return false;
+        };
+        self.render_picker(ui, ctx, &picker);
+        true
+    }
 
-        (String::new(), String::new())
+    /// The workspaces, the monitors or the launch profiles, which the picked app is launched with
+    fn render_picker(&self, ui: &mut Ui, ctx: &mut PluginContext, picker: &LaunchPicker) {
+        ui.add(List::new().with_builder(|list_ui| {
+            for row in &picker.rows {
+                list_ui.row(|row_ui| {
+                    row_ui.label(row.kind);
+                    if row_ui.primary_action(&row.name).activated {
+                        let LaunchPicker {
+                            app, command, args, ..
+                        } = picker;
+                        self.launch_with(row_ui, ctx, app, command, args, &row.choice);
+                    }
+                    row_ui.subtitle(&picker.app.name);
+                });
+            }
+        }));
+    }
+
+    /// The earlier arguments of the app, the files and the launched URLs the arguments can be completed to
+    fn find_completions(&mut self, app: &AppIndex, args: &str) -> Vec<Completion> {
+        let Self {
+            completions,
+            history,
+            ..
+        } = self;
+        completions.get_or_create(format!("{}\0{args}", app.exec), || {
+            let history = history.get_mut();
+            let home = PathBuf::from(env::var("HOME").unwrap_or_default());
+            complete::get_completions(
+                args,
+                history.get_args(app),
+                history.get_urls(),
+                &home,
+                MAX_COMPLETIONS,
+            )
+        })
+    }
+
+    /// Puts the completed arguments after the search of the query
+    fn accept_completion(&self, ctx: &mut PluginContext, keyword: &str, completion: &Completion) {
+        ctx.set_query(format!(
+            "{}{keyword} {}",
+            self.config_prefix, completion.args
+        ));
+        ListState::reset(ctx.egui_ctx, 0);
+    }
+
+    /// The default app of the file or URL, then the other apps which can open it
+    fn render_handlers(
+        &self,
+        row_ui: &mut RowUi,
+        ctx: &mut PluginContext,
+        target: &Target,
+        handlers: &[AppIndex],
+    ) {
+        let Some((default, others)) = handlers.split_first() else {
+            return;
+        };
+        let argument = target.to_argument();
+
+        row_ui.label("Open with");
+        if row_ui.primary_action(&default.name).activated {
+            self.launch(row_ui, ctx, default, &default.exec, &argument);
+        }
+        for app in others {
+            if row_ui.secondary_action(&app.name).activated {
+                self.launch(row_ui, ctx, app, &app.exec, &argument);
+            }
+        }
+    }
+
+    /// The highlights are the matched graphemes of the name
+    fn render_app(
+        &self,
+        row_ui: &mut RowUi,
+        ctx: &mut PluginContext,
+        app: &AppIndex,
+        highlights: &[usize],
+        args: &str,
+    ) {
+        row_ui.label("Launch");
+        if row_ui
+            .primary_action_with_highlights(&app.name, highlights)
+            .activated
+        {
+            self.launch(row_ui, ctx, app, &app.exec, args);
+        }
+        if let Some(sandbox) = app.sandbox {
+            row_ui.subtitle(&sandbox.to_string());
+        }
+
+        for action in &app.actions {
+            if row_ui.secondary_action(&action.name).activated {
+                self.launch(row_ui, ctx, app, &action.command, args);
+            }
+        }
+        if !self.config_launch_profiles.is_empty()
+            && row_ui.secondary_action("Launch with…").activated
+        {
+            let rows = self
+                .config_launch_profiles
+                .iter()
+                .map(|profile| PickerRow {
+                    kind: "Launch with",
+                    name: profile.name.clone(),
+                    choice: LaunchChoice {
+                        placement: None,
+                        profile: Some(profile.name.clone()),
+                    },
+                })
+                .collect();
+            self.open_picker(row_ui, ctx, app, &app.exec, args, rows);
+        }
+    }
+
+    /// Lists the apps of the category, or the main categories starting with the given text
+    fn render_category(&self, ui: &mut Ui, ctx: &mut PluginContext, category: &str, args: &str) {
+        let apps = self.finder.find_by_category(category);
+        if !apps.is_empty() {
+            ui.add(List::new().with_builder(|list_ui| {
+                for app in apps {
+                    list_ui.row(|row_ui| self.render_app(row_ui, ctx, app, &[], args));
+                }
+            }));
+            return;
+        }
+
+        ui.add(List::new().with_builder(|list_ui| {
+            for category in finder::find_main_categories(category) {
+                list_ui.row(|row_ui| {
+                    row_ui.label("Category");
+                    if row_ui.primary_action(category).activated {
+                        ctx.set_query(format!("{}@{category}", self.config_prefix));
+                        ListState::reset(row_ui.ui.ctx(), 0);
+                    }
+                });
+            }
+        }));
+    }
+
+    /// Splits the query into the search and the arguments of the app.
+    /// The search is the first word with the operators around it, like `cat:game !demo steam`,
+    /// or the words joined by `|`.
+    fn split_query(query: &str) -> (String, String) {
+        let tokens: Vec<&str> = query.split(' ').collect();
+        let mut has_search_term = false;
+        let mut end = 0;
+        while let Some(token) = tokens.get(end) {
+            let joined = end > 0 && (tokens[end - 1].ends_with('|') || token.starts_with('|'));
+            let is_search = !token.is_empty() && query::has_operators(token, &finder::FIELDS);
+            if has_search_term && !joined && !is_search {
+                break;
+            }
+            // `!demo` or `cat:game` only filter the apps, the search term can follow them
+            has_search_term |= Query::parse(token, &finder::FIELDS)
+                .groups
+                .iter()
+                .flatten()
+                .any(|term| !term.negated && term.field.is_none());
+            end += 1;
+        }
+
+        (tokens[..end].join(" "), tokens[end..].join(" "))
     }
 }
 
@@ -81,6 +597,9 @@ impl Plugin for Launcher {
         if !ctx.query.starts_with(&self.config_prefix) {
             return;
         }
+        if self.render_open_picker(ui, ctx) {
+            return;
+        }
         let keywords = ctx.query.trim_start_matches(&self.config_prefix);
         let (keyword, args) = Self::split_query(keywords);
         let show_settings = keyword.starts_with(',');
@@ -89,36 +608,134 @@ impl Plugin for Launcher {
             ui.add(List::new().with_builder(|list_ui| {
                 list_ui.row(|ui| {
                     if ui.primary_action("Refresh application cache").activated {
-                        self.reindex_apps()
+                        self.reindex_apps(true)
                     }
                 });
             }));
 
             return;
         }
-        ui.add(List::new().with_builder(|list_ui| {
-            let apps = self.find_apps(&keyword);
-            for app in apps {
-                list_ui.row(|row_ui| {
-                    row_ui.label("Launch");
-                    if row_ui.primary_action(&app.name).activated {
-                        self.run(&app.exec, &args);
-                        ctx.query.clear();
-                        ctx.gl_window.window().set_visible(false);
-                        ListState::reset(row_ui.ui.ctx(), 0);
-                    }
+        if let Some(category) = keyword.strip_prefix('@') {
+            self.render_category(ui, ctx, category, &args);
+            return;
+        }
+
+        // a file or a URL alone, or as the argument of an app
+        let (apps, target) = match Target::parse(keywords) {
+            Some(target) => (vec![], Some(target)),
+            None => (self.find_apps(&keyword), Target::parse(&args)),
+        };
+        let handlers = target
+            .as_ref()
+            .map(|target| self.find_handlers(target))
+            .unwrap_or_default();
+        // after the space following the search, the arguments of the first app are completed
+        let is_typing_args = keywords.len() > keyword.len();
+        let completions = match apps.first() {
+            Some((app, _)) if is_typing_args => self.find_completions(app, &args),
+            _ => vec![],
+        };
+        let tab_pressed = ui.input(|i| i.key_pressed(Key::Tab));
 
-                    for action in &app.actions {
-                        if row_ui.secondary_action(&action.name).activated {
-                            self.run(&action.command, &args);
-                            ctx.query.clear();
-                            ctx.gl_window.window().set_visible(false);
-                            ListState::reset(row_ui.ui.ctx(), 0);
+        let mut accepted = None;
+        ui.add(List::new().with_builder(|list_ui| {
+            if let (Some(target), false) = (&target, handlers.is_empty()) {
+                list_ui.row(|row_ui| self.render_handlers(row_ui, ctx, target, &handlers));
+            }
+            for (i, (app, highlights)) in apps.iter().enumerate() {
+                list_ui.row(|row_ui| self.render_app(row_ui, ctx, app, highlights, &args));
+                if i > 0 {
+                    continue;
+                }
+                for completion in &completions {
+                    list_ui.row(|row_ui| {
+                        row_ui.label("Complete");
+                        let activated = row_ui.primary_action(&completion.label).activated;
+                        if activated || (row_ui.focused && tab_pressed) {
+                            accepted = Some(completion);
                         }
+                    });
+                }
+            }
+        }));
+
+        // Tab accepts the first completion, unless another one is selected
+        if tab_pressed {
+            accepted = accepted.or(completions.first());
+        }
+        if let Some(completion) = accepted {
+            self.accept_completion(ctx, &keyword, completion);
+        }
+    }
+
+    /// The pinned apps, which can be moved by Alt+Up and Alt+Down, then the recently and frequently launched ones
+    fn search_empty(&mut self, ui: &mut Ui, ctx: &mut PluginContext) {
+        if self.render_open_picker(ui, ctx) || self.config_empty_query == EmptyQuery::Nothing {
+            return;
+        }
+        let apps: Vec<&AppIndex> = self
+            .finder
+            .get_apps()
+            .into_iter()
+            .filter(|app| !app.no_display)
+            .collect();
+        // the pinned apps by their position among the pins
+        let pinned: Vec<(usize, &AppIndex)> = self
+            .get_pinned()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, reference)| {
+                let app = apps.iter().find(|app| app.is_referred_by(reference))?;
+                Some((i, *app))
+            })
+            .collect();
+        let recent: Vec<&AppIndex> = self
+            .history
+            .borrow()
+            .get_frequent(&apps, SystemTime::now())
+            .into_iter()
+            .filter(|app| {
+                !pinned
+                    .iter()
+                    .any(|(_, pinned_app)| std::ptr::eq(*pinned_app, *app))
+            })
+            .take(match self.config_empty_query {
+                EmptyQuery::PinnedAndRecent => self.config_number_of_results,
+                EmptyQuery::Pinned | EmptyQuery::Nothing => 0,
+            })
+            .collect();
+        if pinned.is_empty() && recent.is_empty() {
+            return;
+        }
+
+        let mut moved = None;
+        ui.add(List::new().with_builder(|list_ui| {
+            for (position, (_, app)) in pinned.iter().enumerate() {
+                list_ui.row(|row_ui| {
+                    if row_ui.focused {
+                        moved = row_ui.ui.input(|i| {
+                            let is_pressed = |shortcut: &KeyboardShortcut| {
+                                i.modifiers.matches(shortcut.modifiers)
+                                    && i.key_pressed(shortcut.key)
+                            };
+                            let up = is_pressed(&self.config_pin_shortcuts.up);
+                            let down = is_pressed(&self.config_pin_shortcuts.down);
+                            (up || down).then_some((position, up))
+                        });
                     }
+                    self.render_app(row_ui, ctx, app, &[], "");
+                    row_ui.subtitle("pinned");
                 });
             }
+            for app in &recent {
+                list_ui.row(|row_ui| self.render_app(row_ui, ctx, app, &[], ""));
+            }
         }));
+
+        if let Some((position, up)) = moved {
+            let pinned_indices: Vec<usize> = pinned.iter().map(|(i, _)| *i).collect();
+            self.move_pinned(&pinned_indices, position, up);
+        }
     }
 
     fn configure(&mut self, mut builder: ConfigBuilder) -> Result<ConfigBuilder, ConfigError> {
@@ -126,9 +743,108 @@ impl Plugin for Launcher {
             self.config_prefix = builder.get_or_create("prefix", "".to_string())?;
             self.config_index_path = builder.get_or_create("index_path", true)?;
             self.config_number_of_results = builder.get_or_create("number_of_results", 7)?;
-            self.reindex_apps();
+            self.config_terminal_command = builder.get_or_create(
+                "terminal_command",
+                format!(
+                    "${{TERMINAL:-xterm}} -e {}",
+                    exec::TERMINAL_COMMAND_PLACEHOLDER
+                ),
+            )?;
+            self.config_log_output = builder.get_or_create("log_output", false)?;
+            self.config_use_systemd_run = builder.get_or_create("use_systemd_run", false)?;
+            self.config_remember_launches = builder.get_or_create("remember_launches", true)?;
+            self.config_remember_args = builder.get_or_create("remember_args", false)?;
+            self.config_empty_query =
+                builder.get_or_create("empty_query", EmptyQuery::default())?;
+            let pin_shortcuts = PinShortcuts::default();
+            self.config_pin_shortcuts = PinShortcuts {
+                up: builder.get_or_create("move_pinned_up", pin_shortcuts.up)?,
+                down: builder.get_or_create("move_pinned_down", pin_shortcuts.down)?,
+            };
+            self.config_window_timeout =
+                builder.get_or_create("window_timeout", placement::WINDOW_TIMEOUT)?;
+            self.config_entries = builder.get_or_create("entries", Vec::new())?;
+            self.config_launch_profiles = builder.get_or_create("launch_profiles", Vec::new())?;
+            for profile in &self.config_launch_profiles {
+                profile
+                    .validate()
+                    .map_err(|error| ConfigError::BadEntryError {
+                        name: "launch_profiles",
+                        message: Some(format!(
+                            "Wrong config file entry at launch_profiles. {error}"
+                        )),
+                    })?;
+            }
+            self.config_preferences = finder::Preferences {
+                aliases: builder.get_or_create("aliases", HashMap::new())?,
+                blacklist: builder.get_or_create("blacklist", Vec::new())?,
+                pinned: builder.get_or_create("pinned", Vec::new())?,
+                ignore_accents: builder.get_or_create("ignore_accents", true)?,
+                tolerate_typos: builder.get_or_create("tolerate_typos", true)?,
+            };
             Ok(())
         })?;
         Ok(builder)
     }
+
+    fn config_group(&self) -> Option<&'static str> {
+        Some("launcher_plugin")
+    }
+
+    fn setup(&mut self) {
+        self.history = RefCell::new(History::load());
+        self.reindex_apps(false);
+    }
+
+    fn before_search(&mut self, ctx: &mut PluginContext<'_>) {
+        let failures = std::mem::take(&mut *self.failures.lock().unwrap());
+        for failure in &failures {
+            // the app couldn't end its startup notification
+            if let Some(startup_id) = &failure.startup_id {
+                ctx.gl_window
+                    .send_startup_info(&startup::remove_message(startup_id));
+            }
+        }
+        if let Some(failure) = failures.last() {
+            ctx.gl_window.window().set_visible(true);
+            ctx.set_error(format!(
+                "{} couldn't start. {}",
+                failure.name, failure.message
+            ));
+        }
+
+        let update = self
+            .index_watcher
+            .as_ref()
+            .and_then(IndexWatcher::try_get_update);
+        if let Some(apps) = update {
+            self.set_apps(apps);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_query() {
+        let split = Launcher::split_query;
+        let pair = |search: &str, args: &str| (search.to_string(), args.to_string());
+
+        assert_eq!(split(""), pair("", ""));
+        assert_eq!(split("firefox"), pair("firefox", ""));
+        assert_eq!(split("firefox "), pair("firefox", ""));
+        assert_eq!(
+            split("firefox https://example.com"),
+            pair("firefox", "https://example.com")
+        );
+        assert_eq!(
+            split("cat:game !demo steam -silent"),
+            pair("cat:game !demo steam", "-silent")
+        );
+        assert_eq!(split("fire !private ~/a b"), pair("fire !private", "~/a b"));
+        assert_eq!(split("fire | code ."), pair("fire | code", "."));
+        assert_eq!(split("^fire$ x"), pair("^fire$", "x"));
+    }
 }
@@ -0,0 +1,95 @@
+use std::{env, path::PathBuf};
+
+use super::{exec, mime::Database};
+
+/// Schemes which are not followed by `//`
+const OPAQUE_SCHEMES: [&str; 5] = ["mailto", "tel", "sms", "magnet", "geo"];
+
+/// A file or a URL typed into the launcher, which can be opened by the apps of its type
+#[derive(Debug, PartialEq)]
+pub enum Target {
+    File(PathBuf),
+    Url { url: String, scheme: String },
+}
+
+impl Target {
+    /// Only existing absolute paths, or paths starting with `~`, so app names are not taken for files
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(path) = text.strip_prefix("file://") {
+            return Self::parse_path(&exec::percent_decode(path));
+        }
+        if text.starts_with('/') || text.starts_with('~') {
+            return Self::parse_path(text);
+        }
+
+        let (scheme, rest) = text.split_once(':')?;
+        let is_scheme = scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+        let is_url = (rest.starts_with("//") && rest.len() > 2)
+            || (OPAQUE_SCHEMES.contains(&scheme.to_lowercase().as_str()) && !rest.is_empty());
+        (is_scheme && is_url && !text.contains(char::is_whitespace)).then(|| Self::Url {
+            url: text.to_string(),
+            scheme: scheme.to_lowercase(),
+        })
+    }
+
+    fn parse_path(text: &str) -> Option<Self> {
+        let path = match text.strip_prefix('~') {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+                PathBuf::from(format!("{}{rest}", env::var("HOME").ok()?))
+            }
+            Some(_) => return None,
+            None => PathBuf::from(text),
+        };
+        path.exists().then_some(Self::File(path))
+    }
+
+    /// The type of the target, then the broader types
+    pub fn get_mime_types(&self, database: &Database) -> Vec<String> {
+        match self {
+            Self::File(path) => database.get_broader_types(&database.get_mime_type(path)),
+            Self::Url { scheme, .. } => vec![format!("x-scheme-handler/{scheme}")],
+        }
+    }
+
+    /// The quoted argument, like the user would type it
+    pub fn to_argument(&self) -> String {
+        match self {
+            Self::File(path) => exec::quote_arg(&path.to_string_lossy()),
+            Self::Url { url, .. } => exec::quote_arg(url),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Target::parse("/"), Some(Target::File(PathBuf::from("/"))));
+        assert_eq!(
+            Target::parse("file:///tm%70"),
+            Some(Target::File(PathBuf::from("/tmp")))
+        );
+        assert_eq!(
+            Target::parse("https://github.com"),
+            Some(Target::Url {
+                url: "https://github.com".to_string(),
+                scheme: "https".to_string(),
+            })
+        );
+        assert!(matches!(
+            Target::parse("mailto:someone@example.com"),
+            Some(Target::Url { scheme, .. }) if scheme == "mailto"
+        ));
+        assert_eq!(Target::parse("/surely/not/existing"), None);
+        assert_eq!(Target::parse("github.com"), None);
+        assert_eq!(Target::parse("firefox"), None);
+        assert_eq!(Target::parse("c:d"), None);
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    collections::HashSet,
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::windowing::{ewmh::Connection, GlutinWindowContext};
+
+/// The window of a launched app is waited for this long, unless `window_timeout` is set
+pub const WINDOW_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often the windows are checked while waiting
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Where the window of a launched app is moved
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placement {
+    /// the index of the workspace
+    Desktop(usize),
+    /// the top left corner of the monitor
+    Monitor { x: i32, y: i32 },
+}
+
+impl Placement {
+    /// The workspace, which the startup notification can tell the window manager in advance
+    pub fn get_desktop(&self) -> Option<usize> {
+        match self {
+            Self::Desktop(desktop) => Some(*desktop),
+            Self::Monitor { .. } => None,
+        }
+    }
+}
+
+/// A choice of the placement picker
+#[derive(Debug, Clone)]
+pub struct Target {
+    /// "Workspace" or "Monitor"
+    pub kind: &'static str,
+    pub name: String,
+    pub placement: Placement,
+}
+
+/// The workspaces of the window manager, then the monitors if there are more of them
+pub fn get_targets(gl_window: &GlutinWindowContext) -> Vec<Target> {
+    let (count, names) = gl_window.get_desktops();
+    let desktops = get_desktop_names(count, &names)
+        .into_iter()
+        .enumerate()
+        .map(|(i, name)| Target {
+            kind: "Workspace",
+            name,
+            placement: Placement::Desktop(i),
+        });
+
+    let monitors: Vec<_> = gl_window.window().available_monitors().collect();
+    let monitors = monitors
+        .iter()
+        .filter(|_| monitors.len() > 1)
+        .map(|monitor| {
+            let position = monitor.position();
+            Target {
+                kind: "Monitor",
+                name: monitor
+                    .name()
+                    .unwrap_or_else(|| format!("{}, {}", position.x, position.y)),
+                placement: Placement::Monitor {
+                    x: position.x,
+                    y: position.y,
+                },
+            }
+        });
+
+    desktops.chain(monitors).collect()
+}
+
+/// The names of the workspaces, the unnamed ones are called by their number
+fn get_desktop_names(count: usize, names: &[String]) -> Vec<String> {
+    (0..count)
+        .map(|i| match names.get(i) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => (i + 1).to_string(),
+        })
+        .collect()
+}
+
+/// Moves the windows of the launched app when they appear, until the timeout, each of them once.
+/// They are waited for on a thread with its own connection, so the UI is not woken up for it.
+pub fn place_windows(
+    pid: u32,
+    startup_id: Option<String>,
+    placement: Placement,
+    timeout: Duration,
+) {
+    thread::spawn(move || {
+        let Some(connection) = Connection::open() else {
+            eprintln!("Couldn't connect to the X server to move the windows of {pid}");
+            return;
+        };
+        let deadline = Instant::now() + timeout;
+        // the windows moved by the user afterwards are left where they are
+        let mut moved = HashSet::new();
+        while Instant::now() < deadline {
+            for window in connection.find_launched_windows(pid, startup_id.as_deref()) {
+                if !moved.insert(window) {
+                    continue;
+                }
+                match placement {
+                    Placement::Desktop(desktop) => {
+                        connection.move_window_to_desktop(window, desktop)
+                    }
+                    Placement::Monitor { x, y } => connection.move_window_to_position(window, x, y),
+                }
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desktop_names() {
+        // `_NET_DESKTOP_NAMES` ends with a null, and it can have fewer or more names
+        let names = ["web", "", "chat", ""].map(str::to_string);
+        assert_eq!(get_desktop_names(5, &names), ["web", "2", "chat", "4", "5"]);
+        assert_eq!(get_desktop_names(1, &names), ["web"]);
+        assert!(get_desktop_names(0, &names).is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom},
+    os::unix::process::{CommandExt, ExitStatusExt},
+    path::{Path, PathBuf},
+    process::{Child, ChildStderr, Command, ExitStatus, Stdio},
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        mpsc, Arc, Mutex, Once,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The launched processes waiting to be reaped. Zero means a free slot.
+///
+/// The SIGCHLD handler can't lock, so this is a fixed table of atomics.
+/// Only these processes are reaped, so `Command::output()` keeps working everywhere else.
+static CHILDREN: [AtomicI32; 64] = [const { AtomicI32::new(0) }; 64];
+static INSTALL_HANDLER: Once = Once::new();
+
+/// A process exiting within this time after its start is considered a failed launch
+const GRACE_PERIOD: Duration = Duration::from_secs(2);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// The end of the stderr shown of a failed launch
+const MAX_SHOWN_ERROR_OUTPUT: u64 = 2000;
+
+pub struct SpawnOptions<'a> {
+    /// `Path=` of the desktop entry, the home directory is used otherwise
+    pub working_directory: Option<&'a Path>,
+    /// The name of the log file of the output. If it's `None`, the output is dropped.
+    pub log_name: Option<&'a str>,
+    /// Starts the app in its own scope, so it's not killed with the daemon and it's in its own cgroup
+    pub use_systemd_run: bool,
+    /// Passed as `DESKTOP_STARTUP_ID`, so the app can end its startup notification
+    pub startup_id: Option<&'a str>,
+    /// The environment variables set for the app, besides the ones of the daemon
+    pub env: &'a [(String, String)],
+}
+
+/// Starts the process detached from the daemon: in a new session, without the daemon's stdio and working directory.
+///
+/// If it exits unsuccessfully within the grace period, `on_failure` gets the reason and the end of its stderr.
+/// Returns the PID of the process.
+pub fn spawn(
+    argv: &[String],
+    options: &SpawnOptions,
+    on_failure: impl FnOnce(String) + Send + 'static,
+) -> io::Result<u32> {
+    INSTALL_HANDLER.call_once(install_sigchld_handler);
+
+    let systemd_run = ["systemd-run", "--user", "--scope", "--quiet", "--"].map(String::from);
+    let argv: Vec<&String> = if options.use_systemd_run {
+        systemd_run.iter().chain(argv).collect()
+    } else {
+        argv.iter().collect()
+    };
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "The command is empty"))?;
+
+    let (stdout, stderr, log) = match options.log_name {
+        Some(log_name) => {
+            let path = get_log_path(log_name)?;
+            let log = open_append(&path)?;
+            let offset = log.metadata()?.len();
+            (
+                Stdio::from(log.try_clone()?),
+                Stdio::from(log),
+                Some((path, offset)),
+            )
+        }
+        None => (Stdio::null(), Stdio::piped(), None),
+    };
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .current_dir(get_working_directory(options.working_directory))
+        .env_remove("DESKTOP_STARTUP_ID")
+        .stdin(Stdio::null())
+        .stdout(stdout)
+        .stderr(stderr);
+    if let Some(startup_id) = options.startup_id {
+        command.env("DESKTOP_STARTUP_ID", startup_id);
+    }
+    command.envs(options.env.iter().map(|(name, value)| (name, value)));
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+
+    let mut child = command.spawn()?;
+    let error_output = match (log, child.stderr.take()) {
+        (Some((path, offset)), _) => ErrorOutput::Log { path, offset },
+        (None, Some(stderr)) => ErrorOutput::collect(stderr),
+        (None, None) => unreachable!("The stderr of the process is piped"),
+    };
+
+    let pid = child.id();
+    thread::spawn(move || watch_start(child, error_output, on_failure));
+    Ok(pid)
+}
+
+/// Waits for the process during the grace period, then leaves it to the SIGCHLD handler
+fn watch_start(mut child: Child, error_output: ErrorOutput, on_failure: impl FnOnce(String)) {
+    let started = Instant::now();
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => {
+                if !status.success() {
+                    on_failure(describe_failure(status, &error_output.read()));
+                }
+                break;
+            }
+            Ok(None) if started.elapsed() < GRACE_PERIOD => thread::sleep(POLL_INTERVAL),
+            Ok(None) => {
+                register_child(child);
+                break;
+            }
+            Err(error) => {
+                eprintln!("Couldn't check the launched process: {error}");
+                break;
+            }
+        }
+    }
+
+    error_output.discard();
+}
+
+fn register_child(mut child: Child) {
+    let pid = child.id() as i32;
+    let slot = CHILDREN.iter().find(|slot| {
+        slot.compare_exchange(0, pid, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    });
+    match slot {
+        // it might have exited before it was registered
+        Some(_) => reap_children(),
+        // too many running children, this one is waited for on its watching thread instead
+        None => {
+            let _ = child.wait();
+        }
+    }
+}
+
+fn describe_failure(status: ExitStatus, stderr: &str) -> String {
+    let reason = match (status.code(), status.signal()) {
+        (Some(code), _) => format!("It has exited with code {code}."),
+        (None, Some(signal)) => format!("It was killed by signal {signal}."),
+        (None, None) => "It has exited unsuccessfully.".to_string(),
+    };
+    if stderr.is_empty() {
+        reason
+    } else {
+        format!("{reason}\n\n{stderr}")
+    }
+}
+
+/// Where the stderr of a launched process is read from, to show its end if the launch fails
+enum ErrorOutput {
+    /// Appended to a log file, the new output starts at the offset
+    Log { path: PathBuf, offset: u64 },
+    /// Read through a pipe during the grace period. The pipe is drained until the process closes it,
+    /// because closing it would kill the process by SIGPIPE on its next write.
+    Pipe {
+        collected: Arc<Mutex<Option<Vec<u8>>>>,
+        closed: mpsc::Receiver<()>,
+    },
+}
+
+impl ErrorOutput {
+    fn collect(mut stderr: ChildStderr) -> Self {
+        let collected = Arc::new(Mutex::new(Some(Vec::new())));
+        let (sender, closed) = mpsc::channel();
+        let output = collected.clone();
+        thread::spawn(move || {
+            // dropped at the end of the output, which disconnects the receiver
+            let _sender: mpsc::Sender<()> = sender;
+            let mut buffer = [0; 4096];
+            loop {
+                let length = match stderr.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(length) => length,
+                    Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(_) => break,
+                };
+                if let Some(bytes) = output.lock().unwrap().as_mut() {
+                    bytes.extend_from_slice(&buffer[..length]);
+                    let excess = bytes.len().saturating_sub(MAX_SHOWN_ERROR_OUTPUT as usize);
+                    bytes.drain(..excess);
+                }
+            }
+        });
+        Self::Pipe { collected, closed }
+    }
+
+    /// The end of the new output, at most `MAX_SHOWN_ERROR_OUTPUT` bytes
+    fn read(&self) -> String {
+        let bytes = match self {
+            Self::Log { path, offset } => read_end(path, *offset),
+            Self::Pipe { collected, closed } => {
+                // the process has exited, so the rest of its output is about to arrive
+                let _ = closed.recv_timeout(POLL_INTERVAL);
+                collected.lock().unwrap().take().unwrap_or_default()
+            }
+        };
+        String::from_utf8_lossy(&bytes).trim().to_string()
+    }
+
+    /// The later output is read but dropped
+    fn discard(&self) {
+        if let Self::Pipe { collected, .. } = self {
+            collected.lock().unwrap().take();
+        }
+    }
+}
+
+/// The bytes of the file after the offset, at most `MAX_SHOWN_ERROR_OUTPUT` of them from its end
+fn read_end(path: &Path, offset: u64) -> Vec<u8> {
+    let Ok(mut file) = File::open(path) else {
+        return Vec::new();
+    };
+    let length = file.metadata().map(|meta| meta.len()).unwrap_or_default();
+    let start = offset.max(length.saturating_sub(MAX_SHOWN_ERROR_OUTPUT));
+
+    let mut bytes = Vec::new();
+    if file.seek(SeekFrom::Start(start)).is_err() || file.read_to_end(&mut bytes).is_err() {
+        return Vec::new();
+    }
+    bytes
+}
+
+/// A missing directory falls back to the home directory, instead of the directory the daemon was started from
+fn get_working_directory(working_directory: Option<&Path>) -> PathBuf {
+    working_directory
+        .filter(|directory| directory.is_dir())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from(env::var("HOME").unwrap_or_else(|_| "/".into())))
+}
+
+/// `$XDG_STATE_HOME/vonal/logs/<name>.log`
+fn get_log_path(name: &str) -> io::Result<PathBuf> {
+    let state_home = env::var("XDG_STATE_HOME")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            PathBuf::from(env::var("HOME").unwrap_or_default()).join(".local/state")
+        });
+    let directory = state_home.join("vonal").join("logs");
+    fs::create_dir_all(&directory)?;
+
+    let file_name: String = name
+        .chars()
+        .map(|c| {
+            if c == '/' || c.is_whitespace() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    Ok(directory.join(format!("{file_name}.log")))
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn install_sigchld_handler() {
+    extern "C" fn handle_sigchld(_signal: libc::c_int) {
+        reap_children();
+    }
+
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handle_sigchld as extern "C" fn(libc::c_int) as libc::sighandler_t;
+        action.sa_flags = libc::SA_RESTART | libc::SA_NOCLDSTOP;
+        libc::sigemptyset(&mut action.sa_mask);
+        if libc::sigaction(libc::SIGCHLD, &action, std::ptr::null_mut()) == -1 {
+            eprintln!(
+                "Couldn't install the SIGCHLD handler: {}",
+                io::Error::last_os_error()
+            );
+        }
+    }
+}
+
+/// Waits for the exited children without blocking. It's async-signal-safe.
+fn reap_children() {
+    let errno = unsafe { *libc::__errno_location() };
+
+    for slot in &CHILDREN {
+        let pid = slot.load(Ordering::SeqCst);
+        if pid == 0 {
+            continue;
+        }
+        let result = unsafe { libc::waitpid(pid, std::ptr::null_mut(), libc::WNOHANG) };
+        // exited and reaped, or it's not our child anymore
+        if result == pid || result == -1 {
+            slot.store(0, Ordering::SeqCst);
+        }
+    }
+
+    // the interrupted code might check errno
+    unsafe { *libc::__errno_location() = errno };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    fn options() -> SpawnOptions<'static> {
+        SpawnOptions {
+            working_directory: Some(Path::new("/")),
+            log_name: None,
+            use_systemd_run: false,
+            startup_id: None,
+            env: &[],
+        }
+    }
+
+    #[test]
+    fn test_failure_is_reported() {
+        let argv = ["sh", "-c", "echo oops >&2; exit 3"].map(String::from);
+        let (sender, receiver) = mpsc::channel();
+        spawn(&argv, &options(), move |message| {
+            sender.send(message).unwrap()
+        })
+        .unwrap();
+
+        let message = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(message, "It has exited with code 3.\n\noops");
+    }
+
+    #[test]
+    fn test_failure_with_open_output() {
+        // the background process keeps the pipe open after the failure
+        let argv = ["sh", "-c", "sleep 5 & echo oops >&2; exit 3"].map(String::from);
+        let (sender, receiver) = mpsc::channel();
+        spawn(&argv, &options(), move |message| {
+            sender.send(message).unwrap()
+        })
+        .unwrap();
+
+        let message = receiver.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(message, "It has exited with code 3.\n\noops");
+    }
+
+    #[test]
+    fn test_env() {
+        let argv = ["sh", "-c", "echo \"$VONAL_TEST\" >&2; exit 1"].map(String::from);
+        let env = [("VONAL_TEST".to_string(), "a b".to_string())];
+        let options = SpawnOptions {
+            env: &env,
+            ..options()
+        };
+        let (sender, receiver) = mpsc::channel();
+        spawn(&argv, &options, move |message| {
+            sender.send(message).unwrap()
+        })
+        .unwrap();
+
+        let message = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(message.ends_with("a b"));
+    }
+
+    #[test]
+    fn test_success_is_not_reported() {
+        let argv = ["true".to_string()];
+        let (sender, receiver) = mpsc::channel();
+        spawn(&argv, &options(), move |message| {
+            sender.send(message).unwrap()
+        })
+        .unwrap();
+
+        assert!(receiver.recv_timeout(GRACE_PERIOD * 2).is_err());
+    }
+
+    #[test]
+    fn test_missing_program() {
+        let argv = ["vonal-surely-not-installed".to_string()];
+        assert!(spawn(&argv, &options(), |_| {}).is_err());
+    }
+}
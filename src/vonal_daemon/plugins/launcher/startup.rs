@@ -0,0 +1,84 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::indexer::traits::AppIndex;
+
+static LAUNCH_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// A unique ID for every launch, as the startup notification specification requires
+pub fn new_startup_id(app: &AppIndex) -> String {
+    let counter = LAUNCH_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let name: String = app
+        .name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect();
+    format!("vonal-{}-{counter}-{name}", std::process::id())
+}
+
+/// Only the apps which tell that they support it are notified, the rest would never end the startup
+pub fn is_supported(app: &AppIndex) -> bool {
+    app.startup_notify || app.startup_wm_class.is_some()
+}
+
+/// The `new:` message, which starts the busy cursor or the launch feedback of the window manager.
+/// The desktop is the workspace the window should be mapped on.
+pub fn new_message(
+    id: &str,
+    app: &AppIndex,
+    binary: &str,
+    screen: i32,
+    desktop: Option<usize>,
+) -> String {
+    let mut message = format!("new: ID={} NAME={}", quote(id), quote(&app.name));
+    message.push_str(&format!(" SCREEN={screen} BIN={}", quote(binary)));
+    if let Some(desktop) = desktop {
+        message.push_str(&format!(" DESKTOP={desktop}"));
+    }
+    if let Some(icon) = &app.icon {
+        message.push_str(&format!(" ICON={}", quote(icon)));
+    }
+    if let Some(wm_class) = &app.startup_wm_class {
+        message.push_str(&format!(" WMCLASS={}", quote(wm_class)));
+    }
+    if let Some(desktop_file) = app.desktop_file.as_ref().and_then(|path| path.to_str()) {
+        message.push_str(&format!(" APPLICATION_ID={}", quote(desktop_file)));
+    }
+    message
+}
+
+/// The `remove:` message, which ends the startup sequence if the app couldn't do it
+pub fn remove_message(id: &str) -> String {
+    format!("remove: ID={}", quote(id))
+}
+
+fn quote(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{escaped}\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        let app = AppIndex {
+            exec: "gimp %U".to_string(),
+            name: "GNU \"Image\" Editor".to_string(),
+            icon: Some("gimp".to_string()),
+            desktop_file: Some(PathBuf::from("/usr/share/applications/gimp.desktop")),
+            startup_notify: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            new_message("vonal-1-0-GNUImageEditor", &app, "gimp", 0, None),
+            r#"new: ID="vonal-1-0-GNUImageEditor" NAME="GNU \"Image\" Editor" SCREEN=0 BIN="gimp" ICON="gimp" APPLICATION_ID="/usr/share/applications/gimp.desktop""#
+        );
+        assert!(new_message("id", &app, "gimp", 0, Some(2)).contains(" BIN=\"gimp\" DESKTOP=2 "));
+        assert_eq!(remove_message("a\\b"), r#"remove: ID="a\\b""#);
+        assert_ne!(new_startup_id(&app), new_startup_id(&app));
+    }
+}
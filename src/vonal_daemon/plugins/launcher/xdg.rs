@@ -0,0 +1,28 @@
+use std::{env, path::PathBuf};
+
+/// `$XDG_DATA_HOME`, or `~/.local/share`
+pub fn get_data_home() -> PathBuf {
+    env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(env::var("HOME").unwrap_or_default()).join(".local/share"))
+}
+
+/// `$XDG_DATA_HOME`, then `$XDG_DATA_DIRS`
+pub fn get_data_directories() -> Vec<PathBuf> {
+    let data_directories = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|paths| !paths.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+
+    [get_data_home()]
+        .into_iter()
+        .chain(
+            data_directories
+                .split(':')
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+        )
+        .collect()
+}
@@ -1,6 +1,7 @@
-use std::error::Error;
+use std::{collections::HashMap, error::Error};
 
 use egui::{Context, Ui};
+use toml::Value;
 
 use crate::{
     config::{ConfigBuilder, ConfigError},
@@ -29,6 +30,7 @@ pub struct PluginContext<'a> {
     flow: PluginFlowControl,
     pub egui_ctx: &'a Context,
     disable_cursor: bool,
+    reset_search_input_cursor: bool,
     error: Option<String>,
 }
 
@@ -44,6 +46,7 @@ impl<'a> PluginContext<'a> {
             query,
             egui_ctx,
             disable_cursor: false,
+            reset_search_input_cursor: false,
             error: Default::default(),
         }
     }
@@ -54,6 +57,12 @@ impl<'a> PluginContext<'a> {
     pub fn disable_cursor(&mut self) {
         self.disable_cursor = true;
     }
+    /// Replaces the query, and moves the cursor to its end
+    pub fn set_query(&mut self, query: String) {
+        *self.query = query;
+        self.reset_search_input_cursor = true;
+        self.egui_ctx.request_repaint();
+    }
     pub fn set_error(&mut self, message: String) {
         self.error = Some(message);
         self.break_flow();
@@ -85,10 +94,21 @@ pub trait Plugin {
     ///     Ok(builder)
     /// }
     /// ```
+    ///
+    /// It should be cheap, because the config is validated by configuring fresh plugins
+    /// before the running ones are configured. Expensive preparation belongs to [`Plugin::setup`].
     fn configure(&mut self, builder: ConfigBuilder) -> Result<ConfigBuilder, ConfigError> {
         Ok(builder)
     }
+    /// The config group of the plugin. [`Plugin::setup`] only runs when this group has changed.
+    fn config_group(&self) -> Option<&'static str> {
+        None
+    }
+    /// Expensive preparation (like indexing) after a successful configuration.
+    fn setup(&mut self) {}
     fn search(&mut self, ui: &mut Ui, ctx: &mut PluginContext<'_>);
+    /// Called instead of [`Plugin::search`] when the query is empty, e.g. to list the recently used items
+    fn search_empty(&mut self, _ui: &mut Ui, _ctx: &mut PluginContext<'_>) {}
     fn before_search(&mut self, _ctx: &mut PluginContext<'_>) {}
 }
 
@@ -96,6 +116,8 @@ pub trait Plugin {
 pub struct PluginManager {
     plugins: Vec<Box<dyn Plugin>>,
     config_plugins: Vec<String>,
+    /// the config groups of the plugins at their last setup
+    setup_groups: HashMap<&'static str, Option<Value>>,
     /// the nth plugin returned PluginFlowControl::break during search
     flow_broke_at: usize,
 }
@@ -119,6 +141,7 @@ impl PluginManager {
 
         if self.config_plugins != plugins {
             self.plugins = Vec::new();
+            self.setup_groups.clear();
 
             for plugin in &plugins {
                 match plugin.as_str() {
@@ -147,17 +170,52 @@ impl PluginManager {
         Ok(builder)
     }
 
+    /// Takes the plugins of the freshly configured manager, and runs their setup.
+    /// The running plugins whose config group hasn't changed since their last setup are kept instead,
+    /// with their state.
+    pub fn replace(&mut self, configured: PluginManager, builder: &ConfigBuilder) {
+        let same_plugins = self.config_plugins == configured.config_plugins;
+        let mut running: Vec<Option<Box<dyn Plugin>>> = if same_plugins {
+            std::mem::take(&mut self.plugins)
+                .into_iter()
+                .map(Some)
+                .collect()
+        } else {
+            self.setup_groups.clear();
+            vec![]
+        };
+
+        self.plugins = Vec::with_capacity(configured.plugins.len());
+        for (i, mut plugin) in configured.plugins.into_iter().enumerate() {
+            if let Some(group) = plugin.config_group() {
+                let config = builder.get(group).cloned();
+                let unchanged = self.setup_groups.get(group) == Some(&config);
+                if let Some(running) = running
+                    .get_mut(i)
+                    .and_then(Option::take)
+                    .filter(|_| unchanged)
+                {
+                    self.plugins.push(running);
+                    continue;
+                }
+                self.setup_groups.insert(group, config);
+            }
+            plugin.setup();
+            self.plugins.push(plugin);
+        }
+        self.config_plugins = configured.config_plugins;
+    }
+
     pub fn search(&mut self, ui: &mut Ui, ctx: &mut PluginContext<'_>) -> PostOperation {
         ui.horizontal_top(|ui| {
             ui.add_space(15.);
             ui.vertical(|ui| {
-                // don't search when there's nothing to search
-                if ctx.query.is_empty() {
-                    return;
-                }
-
                 for (i, plugin) in &mut self.plugins.iter_mut().enumerate() {
-                    plugin.search(ui, ctx);
+                    if ctx.query.is_empty() {
+                        plugin.search_empty(ui, ctx);
+                    } else {
+                        plugin.search(ui, ctx);
+                    }
                     if let PluginFlowControl::Break = ctx.flow {
                         // Clear the list on switching between plugins
                         if self.flow_broke_at != i {
@@ -174,6 +232,7 @@ impl PluginManager {
 
         PostOperation {
             error: ctx.error.clone(),
+            reset_search_input_cursor: ctx.reset_search_input_cursor,
         }
     }
 
@@ -187,6 +246,7 @@ impl PluginManager {
 
         Preparation {
             disable_cursor: ctx.disable_cursor,
+            error: ctx.error.clone(),
         }
     }
 }
@@ -195,7 +255,11 @@ pub struct Preparation {
     /// if you move the focus by arrow keys, you have to hide the cursor.
     /// otherwise the cursor will be jumping
     pub disable_cursor: bool,
+    /// a plugin has failed outside of the search, e.g. a launched app has exited
+    pub error: Option<String>,
 }
 pub struct PostOperation {
     pub error: Option<String>,
+    /// a plugin has replaced the query
+    pub reset_search_input_cursor: bool,
 }
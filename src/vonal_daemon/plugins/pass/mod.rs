@@ -1,6 +1,7 @@
 use crate::{
     config::{ConfigBuilder, ConfigError},
     theme::list::{self, ListState},
+    utils::search::query::Filter,
 };
 use std::{
     error::Error,
@@ -38,6 +39,8 @@ struct MessageState {
 #[derive(Default)]
 pub struct Pass {
     message: Option<MessageState>,
+    /// filters the names of the passwords, reusing the matches of the earlier queries
+    passwords: Filter<String>,
     config_command_list_passwords: String,
     config_command_copy_password: String,
     config_command_type_password: String,
@@ -53,7 +56,7 @@ impl Pass {
         Default::default()
     }
 
-    fn list_passwords(&self, keyword: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    fn list_passwords(&self) -> Result<Vec<String>, Box<dyn Error>> {
         let call = Command::new("bash")
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
@@ -68,12 +71,18 @@ impl Pass {
         }
 
         let stdout = String::from_utf8_lossy(&call.stdout);
-        let passwords = stdout
-            .lines()
-            .filter(|name| keyword.is_empty() || name.contains(keyword))
-            .map(ToString::to_string)
-            .collect();
-        Ok(passwords)
+        Ok(stdout.lines().map(ToString::to_string).collect())
+    }
+
+    fn find_passwords(&mut self, keyword: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        self.passwords.set_items(self.list_passwords()?);
+        // the names are matched as a whole, so `'mail` finds `work/email`
+        Ok(self
+            .passwords
+            .filter(keyword)
+            .into_iter()
+            .cloned()
+            .collect())
     }
 
     fn copy_password(&self, pw: &str) -> Result<(), std::io::Error> {
@@ -173,7 +182,7 @@ impl Pass {
     }
 
     fn is_existing_password(&self, password: &str) -> Result<bool, Box<dyn Error>> {
-        Ok(self.list_passwords("")?.iter().any(|pw| pw == password))
+        Ok(self.list_passwords()?.iter().any(|pw| pw == password))
     }
 
     fn render_generate_button(
@@ -307,7 +316,7 @@ impl Plugin for Pass {
             keyword
         };
 
-        match self.list_passwords(&password_name) {
+        match self.find_passwords(&password_name) {
             Ok(passwords) => {
                 ui.add(
                     list::List::new()
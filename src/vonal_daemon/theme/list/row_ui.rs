@@ -1,4 +1,7 @@
-use egui::{Button, Color32, Ui};
+use egui::{
+    text::LayoutJob, Button, Color32, RichText, Stroke, TextFormat, TextStyle, Ui, WidgetText,
+};
+use unicode_segmentation::UnicodeSegmentation;
 
 pub struct RowUi<'a> {
     pub ui: &'a mut Ui,
@@ -16,7 +19,20 @@ impl<'a> RowUi<'a> {
     pub fn label(&mut self, name: &str) {
         self.ui.colored_label(Color32::from_gray(200), name);
     }
+    /// Dim text after the actions, which tells more about the row
+    pub fn subtitle(&mut self, text: &str) {
+        self.ui
+            .label(RichText::new(text).small().color(Color32::from_gray(130)));
+    }
     pub fn primary_action(&mut self, name: &str) -> RowUiAction {
+        self.primary_action_with_highlights(name, &[])
+    }
+    /// The graphemes of the name at the positions are underlined, like the letters matching the query
+    pub fn primary_action_with_highlights(
+        &mut self,
+        name: &str,
+        highlights: &[usize],
+    ) -> RowUiAction {
         let focused = self.focused && self.focused_col == self.col;
         let bg = Color32::from_black_alpha(0);
         let fg = match focused {
@@ -28,7 +44,11 @@ impl<'a> RowUi<'a> {
             .ui
             .scope(|ui| {
                 ui.visuals_mut().override_text_color = Some(fg);
-                let action_btn = Button::new(name).fill(bg);
+                let text = match highlights {
+                    [] => WidgetText::from(name),
+                    _ => highlight(ui, name, highlights, fg).into(),
+                };
+                let action_btn = Button::new(text).fill(bg);
 
                 ui.add(action_btn).clicked() || (focused && self.enter_pressed)
             })
@@ -61,6 +81,34 @@ impl<'a> RowUi<'a> {
     }
 }
 
+/// The text of a button with the graphemes at the positions underlined
+fn highlight(ui: &Ui, text: &str, positions: &[usize], color: Color32) -> LayoutJob {
+    let mut job = LayoutJob::default();
+    let format = |underline| TextFormat {
+        font_id: TextStyle::Button.resolve(ui.style()),
+        color,
+        underline: if underline {
+            Stroke::new(1.0, color)
+        } else {
+            Stroke::NONE
+        },
+        ..Default::default()
+    };
+
+    // the runs of highlighted and other graphemes
+    let mut start = 0;
+    let mut highlighted = false;
+    for (i, (offset, _)) in text.grapheme_indices(true).enumerate() {
+        let is_highlighted = positions.contains(&i);
+        if is_highlighted != highlighted {
+            job.append(&text[start..offset], 0.0, format(highlighted));
+            (start, highlighted) = (offset, is_highlighted);
+        }
+    }
+    job.append(&text[start..], 0.0, format(highlighted));
+    job
+}
+
 pub struct RowUiAction {
     pub activated: bool,
 }
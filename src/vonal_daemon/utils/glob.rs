@@ -0,0 +1,140 @@
+//! Shell style patterns with `*`, `?` and `[...]`, like the globs of shared-mime-info or the launch profiles
+
+enum Token {
+    /// `*`, any number of chars
+    Star,
+    /// `?`, a single char
+    Any,
+    /// `[a-z_]` or `[!0-9]`
+    Set {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Char(char),
+}
+
+impl Token {
+    /// Whether the token matches the char. The `*` is handled by [`matches`].
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Token::Star | Token::Any => true,
+            Token::Set { negated, ranges } => {
+                ranges.iter().any(|&(from, to)| (from..=to).contains(&c)) != *negated
+            }
+            Token::Char(token) => *token == c,
+        }
+    }
+}
+
+/// Whether the whole name matches the pattern.
+/// A `*` matches nothing first, and when the rest doesn't match, the last `*` takes one more char,
+/// so it's checked without recursion, in at most the product of the lengths.
+pub fn matches(pattern: &str, name: &str) -> bool {
+    let tokens = parse(pattern);
+    let name: Vec<char> = name.chars().collect();
+
+    let mut token = 0;
+    let mut position = 0;
+    // the token after the last `*`, and where the name continues after the chars it took
+    let mut backtrack: Option<(usize, usize)> = None;
+    while position < name.len() {
+        match tokens.get(token) {
+            Some(Token::Star) => {
+                token += 1;
+                backtrack = Some((token, position));
+            }
+            Some(current) if current.matches(name[position]) => {
+                token += 1;
+                position += 1;
+            }
+            _ => {
+                let Some((after_star, taken)) = backtrack else {
+                    return false;
+                };
+                token = after_star;
+                position = taken + 1;
+                backtrack = Some((after_star, position));
+            }
+        }
+    }
+    tokens[token..]
+        .iter()
+        .all(|token| matches!(token, Token::Star))
+}
+
+/// A `[` without a closing `]` is a literal char
+fn parse(pattern: &str) -> Vec<Token> {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut tokens = Vec::with_capacity(chars.len());
+
+    let mut i = 0;
+    while i < chars.len() {
+        let set_end = (chars[i] == '[')
+            .then(|| chars[i + 1..].iter().position(|c| *c == ']'))
+            .flatten();
+        match (chars[i], set_end) {
+            ('*', _) => tokens.push(Token::Star),
+            ('?', _) => tokens.push(Token::Any),
+            ('[', Some(length)) => {
+                let set = &chars[i + 1..i + 1 + length];
+                let (negated, set) = match set.split_first() {
+                    Some(('!', set)) => (true, set),
+                    _ => (false, set),
+                };
+                tokens.push(Token::Set {
+                    negated,
+                    ranges: parse_ranges(set),
+                });
+                i += length + 1;
+            }
+            (c, _) => tokens.push(Token::Char(c)),
+        }
+        i += 1;
+    }
+    tokens
+}
+
+/// `a-z` is a range, and the other chars stand for themselves
+fn parse_ranges(set: &[char]) -> Vec<(char, char)> {
+    let mut ranges = Vec::new();
+    let mut i = 0;
+    while i < set.len() {
+        if i + 2 < set.len() && set[i + 1] == '-' {
+            ranges.push((set[i], set[i + 2]));
+            i += 3;
+        } else {
+            ranges.push((set[i], set[i]));
+            i += 1;
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("*.pdf", "report.pdf"));
+        assert!(!matches("*.pdf", "report.pdf.txt"));
+        assert!(matches("makefile", "makefile"));
+        assert!(matches("*.[ch]", "main.c"));
+        assert!(matches("*.[!ch]", "main.o"));
+        assert!(matches("*.m[0-9]a", "song.m4a"));
+        assert!(matches("?.txt", "a.txt"));
+        assert!(!matches("?.txt", ".txt"));
+        assert!(matches("*steam*", "/usr/bin/steam-runtime %U"));
+        assert!(matches("a[b", "a[b"));
+        assert!(matches("**", ""));
+        assert!(!matches("", "a"));
+    }
+
+    #[test]
+    fn test_many_stars() {
+        // every split of the name would be tried by a recursive matcher
+        let name = "a".repeat(1000);
+        assert!(!matches(&format!("{}b", "*a".repeat(20)), &name));
+        assert!(matches(&"*a".repeat(20), &name));
+    }
+}
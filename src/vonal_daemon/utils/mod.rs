@@ -1 +1,3 @@
-pub mod clipboard;
\ No newline at end of file
+pub mod clipboard;
+pub mod glob;
+pub use vonal_search as search;
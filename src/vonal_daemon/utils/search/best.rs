@@ -0,0 +1,51 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Keeps the best items of a search by their fitness, the earlier item wins a tie
+pub struct Best {
+    limit: usize,
+    /// a min-heap, so the worst one is dropped when there are too many
+    heap: BinaryHeap<Reverse<(i32, Reverse<usize>)>>,
+}
+
+impl Best {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: BinaryHeap::with_capacity(limit + 1),
+        }
+    }
+
+    pub fn push(&mut self, fitness: i32, item: usize) {
+        self.heap.push(Reverse((fitness, Reverse(item))));
+        if self.heap.len() > self.limit {
+            self.heap.pop();
+        }
+    }
+
+    /// The items, the best first
+    pub fn into_sorted_vec(self) -> Vec<usize> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((_, Reverse(item)))| item)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_best() {
+        let mut best = Best::new(3);
+        for (item, fitness) in [5, 1, 9, 5, 7, 9].into_iter().enumerate() {
+            best.push(fitness, item);
+        }
+        assert_eq!(best.into_sorted_vec(), vec![2, 5, 4]);
+
+        let mut best = Best::new(0);
+        best.push(1, 0);
+        assert!(best.into_sorted_vec().is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::VecDeque;
+
+/// The number of keys a `PrefixCache` remembers
+const RECENT_KEYS: usize = 16;
+
+/// Remembers the values of the recently used keys, and finds the longest remembered prefix of a key.
+/// So a query which extends an earlier one can start from its results,
+/// and the earlier queries are still there after a backspace.
+pub struct PrefixCache<K, V> {
+    /// the most recently used first
+    entries: VecDeque<(Vec<K>, V)>,
+}
+
+impl<K, V> Default for PrefixCache<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: VecDeque::with_capacity(RECENT_KEYS),
+        }
+    }
+}
+
+impl<K: PartialEq, V> PrefixCache<K, V> {
+    /// The value of the key, which becomes the most recently used
+    pub fn get(&mut self, key: &[K]) -> Option<&V> {
+        let position = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(position)?;
+        self.entries.push_front(entry);
+        self.entries.front().map(|(_, value)| value)
+    }
+
+    /// The value of the longest remembered key which the key starts with, or which equals to it
+    pub fn get_longest_prefix(&self, key: &[K]) -> Option<&V> {
+        self.entries
+            .iter()
+            .filter(|(k, _)| key.starts_with(k))
+            .max_by_key(|(k, _)| k.len())
+            .map(|(_, value)| value)
+    }
+
+    /// The least recently used key is forgotten if there are too many
+    pub fn insert(&mut self, key: Vec<K>, value: V) {
+        self.entries.retain(|(k, _)| *k != key);
+        self.entries.push_front((key, value));
+        self.entries.truncate(RECENT_KEYS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefix_cache() {
+        let mut cache = PrefixCache::default();
+        cache.insert(b"f".to_vec(), 1);
+        cache.insert(b"fir".to_vec(), 3);
+        cache.insert(b"x".to_vec(), 0);
+
+        assert_eq!(cache.get_longest_prefix(b"fire"), Some(&3));
+        assert_eq!(cache.get_longest_prefix(b"fi"), Some(&1));
+        assert_eq!(cache.get_longest_prefix(b"fir"), Some(&3));
+        assert_eq!(cache.get_longest_prefix(b"a"), None);
+        assert_eq!(cache.get(b"fi"), None);
+
+        // the least recently used ones are forgotten
+        assert_eq!(cache.get(b"f"), Some(&1));
+        for i in 0..RECENT_KEYS - 1 {
+            cache.insert(vec![b'a'; i + 1], i);
+        }
+        assert_eq!(cache.get(b"f"), Some(&1));
+        assert_eq!(cache.get(b"x"), None);
+    }
+}
@@ -0,0 +1,712 @@
+//! A Smith-Waterman style fuzzy matcher, scored like fzf:
+//! the matches at the start of the words, at camelCase humps and in consecutive runs are worth more.
+//!
+//! The query doesn't need to match entirely, the letters missing from the text or out of order
+//! are skipped with a penalty. So `clomium` still finds `chromium`, just with a lower fitness.
+//! Only the first letter can't be skipped, so the texts matching a query match the shorter queries
+//! it starts with too, and the finder can narrow the results of the previous query while typing.
+//!
+//! Optionally the typos are tolerated too: if the letters of the query are too scattered in the text,
+//! the query is compared with the start of every word by edit distance, see `typo`.
+
+use std::{
+    cmp::max,
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::typo;
+
+/// The scores are multiplied by this, so the penalties of the finder can be finer than a letter
+const UNIT: i32 = 64;
+
+const SCORE_MATCH: i32 = 16 * UNIT;
+const PENALTY_GAP_START: i32 = 3 * UNIT;
+const PENALTY_GAP_EXTENSION: i32 = UNIT;
+/// A query letter which can't be matched in order
+const PENALTY_SKIP: i32 = 8 * UNIT;
+
+const BONUS_BOUNDARY: i32 = 8 * UNIT;
+const BONUS_BOUNDARY_WHITE: i32 = 10 * UNIT;
+const BONUS_BOUNDARY_DELIMITER: i32 = 9 * UNIT;
+/// Slightly less than a boundary, like `Code` in `VSCode` or `2` in `mp2`
+const BONUS_CAMEL_123: i32 = BONUS_BOUNDARY - PENALTY_GAP_EXTENSION;
+/// Compensates the gap, so `fo` prefers `foo` over `f-o`
+const BONUS_CONSECUTIVE: i32 = PENALTY_GAP_START + PENALTY_GAP_EXTENSION;
+/// The first letter of the query counts more, it's usually typed intentionally
+const BONUS_FIRST_CHAR_MULTIPLIER: i32 = 2;
+
+/// Punishes the late matches, per grapheme before the first match
+const PENALTY_LATE_MATCH: i32 = 30;
+
+/// A typo costs as much as a letter of the query
+const PENALTY_TYPO: i32 = SCORE_MATCH;
+
+/// In graphemes
+const MAX_NAME_LENGTH: usize = 127;
+
+const NONE: i32 = i32::MIN / 4;
+
+#[derive(Clone, Copy, PartialEq)]
+enum CharClass {
+    White,
+    Delimiter,
+    NonWord,
+    Lower,
+    Upper,
+    Letter,
+    Number,
+}
+
+impl CharClass {
+    fn of(grapheme: &str) -> Self {
+        let c = grapheme.chars().next().unwrap_or(' ');
+        if c.is_whitespace() {
+            Self::White
+        } else if "/,:;|".contains(c) {
+            Self::Delimiter
+        } else if c.is_lowercase() {
+            Self::Lower
+        } else if c.is_uppercase() {
+            Self::Upper
+        } else if c.is_numeric() {
+            Self::Number
+        } else if c.is_alphabetic() {
+            Self::Letter
+        } else {
+            Self::NonWord
+        }
+    }
+
+    fn is_word(self) -> bool {
+        !matches!(self, Self::White | Self::Delimiter | Self::NonWord)
+    }
+}
+
+/// The bonus of a grapheme by its class and the class of the grapheme before it
+fn get_bonus(previous: CharClass, current: CharClass) -> i32 {
+    if current.is_word() {
+        match previous {
+            CharClass::White => return BONUS_BOUNDARY_WHITE,
+            CharClass::Delimiter => return BONUS_BOUNDARY_DELIMITER,
+            CharClass::NonWord => return BONUS_BOUNDARY,
+            _ => {}
+        }
+    }
+    if (previous == CharClass::Lower && current == CharClass::Upper)
+        || (previous != CharClass::Number && current == CharClass::Number)
+    {
+        return BONUS_CAMEL_123;
+    }
+    match current {
+        CharClass::NonWord | CharClass::Delimiter => BONUS_BOUNDARY,
+        CharClass::White => BONUS_BOUNDARY_WHITE,
+        _ => 0,
+    }
+}
+
+/// A text prepared for matching: one unit for every grapheme cluster, in lower case,
+/// and optionally without accents, so `e` matches `é`.
+/// A grapheme can become more units, like `ß` becomes `ss`.
+///
+/// The texts of the apps are folded once, when the finder is made.
+#[derive(Debug, Clone, Default)]
+pub struct Folded {
+    units: Vec<u32>,
+    bonuses: Vec<i32>,
+    /// the index of the original grapheme of each unit
+    graphemes: Vec<usize>,
+    /// a bit for every unit modulo 64, to skip the texts without any common letters
+    mask: u64,
+    /// the units starting a word or a camelCase hump, for the typos
+    word_starts: Vec<usize>,
+}
+
+impl Folded {
+    pub fn new(text: &str, strip_accents: bool) -> Self {
+        let mut folded = Self::default();
+        let mut previous_class = CharClass::White;
+
+        for (index, grapheme) in text.graphemes(true).enumerate() {
+            let class = CharClass::of(grapheme);
+            let mut bonus = get_bonus(previous_class, class);
+            if class.is_word() && (bonus > 0 || folded.units.is_empty()) {
+                folded.word_starts.push(folded.units.len());
+            }
+            previous_class = class;
+
+            for unit in fold(grapheme, strip_accents) {
+                if folded.units.len() == MAX_NAME_LENGTH {
+                    return folded;
+                }
+                folded.mask |= 1 << (unit % 64);
+                folded.units.push(unit);
+                folded.bonuses.push(bonus);
+                folded.graphemes.push(index);
+                // the rest of an expanded grapheme is not a boundary
+                bonus = 0;
+            }
+        }
+        folded
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.units.is_empty()
+    }
+
+    /// Like `str::contains`, but the case and the accents don't count
+    pub fn contains(&self, other: &Folded) -> bool {
+        other.is_empty()
+            || self
+                .units
+                .windows(other.units.len())
+                .any(|window| window == other.units)
+    }
+
+    pub fn starts_with(&self, other: &Folded) -> bool {
+        self.units.starts_with(&other.units)
+    }
+
+    pub fn ends_with(&self, other: &Folded) -> bool {
+        self.units.ends_with(&other.units)
+    }
+
+    pub fn is_same_text(&self, other: &Folded) -> bool {
+        self.units == other.units
+    }
+}
+
+/// The units of a grapheme. Graphemes of a single char are the char itself, the others are hashed.
+fn fold(grapheme: &str, strip_accents: bool) -> Vec<u32> {
+    // most of the names are ASCII
+    if let [byte] = grapheme.as_bytes() {
+        return vec![byte.to_ascii_lowercase() as u32];
+    }
+
+    let mut lowercase = String::with_capacity(grapheme.len());
+    for c in grapheme.chars() {
+        // the case folding of Unicode differs from the lowercase only in a few letters
+        match c {
+            'ß' | 'ẞ' => lowercase.push_str("ss"),
+            'ς' => lowercase.push('σ'),
+            c => lowercase.extend(c.to_lowercase()),
+        }
+    }
+
+    lowercase
+        .graphemes(true)
+        .map(|grapheme| {
+            let mut folded: String = grapheme.nfc().collect();
+            if strip_accents {
+                let base: String = grapheme.nfd().filter(|c| !is_combining_mark(*c)).collect();
+                // a grapheme of marks only is kept
+                if !base.is_empty() {
+                    folded = base;
+                }
+            }
+            to_unit(&folded)
+        })
+        .collect()
+}
+
+fn to_unit(grapheme: &str) -> u32 {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => c as u32,
+        _ => {
+            let mut hasher = DefaultHasher::new();
+            grapheme.hash(&mut hasher);
+            // above the range of chars
+            0x8000_0000 | hasher.finish() as u32
+        }
+    }
+}
+
+/// Matches a query with many texts. It keeps its buffers between the texts.
+pub struct Matcher {
+    query: Folded,
+    /// the best score when the query unit is matched at the text unit
+    matched: Vec<i32>,
+    /// the best score when the last match of the query so far is at the text unit
+    last: Vec<i32>,
+    /// the best score when the last match of the query so far is before the text unit
+    gap: Vec<i32>,
+    tolerates_typos: bool,
+    /// the buffers of the edit distance
+    typo_rows: [Vec<usize>; 3],
+}
+
+impl Matcher {
+    pub fn new(query: &str, strip_accents: bool) -> Self {
+        Self {
+            query: Folded::new(query, strip_accents),
+            matched: vec![],
+            last: vec![],
+            gap: vec![],
+            tolerates_typos: false,
+            typo_rows: Default::default(),
+        }
+    }
+
+    /// The texts are compared by edit distance too, if the query is scattered in them
+    pub fn set_typos(&mut self, tolerates_typos: bool) {
+        self.tolerates_typos = tolerates_typos;
+    }
+
+    /// The folded query, a longer query extending it has these units first
+    pub fn units(&self) -> &[u32] {
+        &self.query.units
+    }
+
+    pub fn query(&self) -> &Folded {
+        &self.query
+    }
+
+    /// The fitness of the text, or `None` if no letter of the query is found in it.
+    /// A typo only raises the fitness of a matching text, so narrowing the results stays valid.
+    pub fn get_fitness(&mut self, text: &Folded) -> Option<i32> {
+        let (mut score, _) = self.align(text)?;
+        if self.tolerates_typos && score < self.get_max_typo_score() {
+            score = max(score, self.get_typo_score(text).unwrap_or(NONE));
+        }
+        Some(score - text.units.len() as i32)
+    }
+
+    /// The indices of the matched graphemes of the text, or `None` if no letter of the query is found in it
+    pub fn get_positions(&mut self, text: &Folded) -> Option<Vec<usize>> {
+        let (_, end) = self.align(text)?;
+        let mut positions: Vec<usize> = self
+            .trace_back(text, end)
+            .into_iter()
+            .map(|unit| text.graphemes[unit])
+            .collect();
+        positions.dedup();
+        Some(positions)
+    }
+
+    /// A typo match is scored like a run of the query at the start of a word, minus the typos
+    fn get_typo_score_at(&self, text: &Folded, start: usize, distance: usize) -> i32 {
+        let length = self.query.units.len() as i32;
+        length * SCORE_MATCH
+            + text.bonuses[start] * BONUS_FIRST_CHAR_MULTIPLIER
+            + (length - 1) * BONUS_CONSECUTIVE
+            - distance as i32 * PENALTY_TYPO
+            - PENALTY_LATE_MATCH * text.graphemes[start] as i32
+    }
+
+    /// The best a typo can score, a better fuzzy match is kept without computing the distances
+    fn get_max_typo_score(&self) -> i32 {
+        let length = self.query.units.len() as i32;
+        length * SCORE_MATCH
+            + BONUS_BOUNDARY_WHITE * BONUS_FIRST_CHAR_MULTIPLIER
+            + (length - 1) * BONUS_CONSECUTIVE
+            - PENALTY_TYPO
+    }
+
+    /// The score of the word starting the most like the query, or `None` if it has too many typos
+    fn get_typo_score(&mut self, text: &Folded) -> Option<i32> {
+        let max_distance = typo::get_max_distance(self.query.units.len());
+        if max_distance == 0 {
+            return None;
+        }
+
+        let mut best = None;
+        for &start in &text.word_starts {
+            let distance = typo::get_prefix_distance(
+                &self.query.units,
+                &text.units[start..],
+                max_distance,
+                &mut self.typo_rows,
+            );
+            if let Some(distance) = distance {
+                let score = self.get_typo_score_at(text, start, distance);
+                best = max(best, Some(score));
+            }
+        }
+        best
+    }
+
+    /// Fills the tables of the alignment, and returns the best score and the end of its last match
+    fn align(&mut self, text: &Folded) -> Option<(i32, usize)> {
+        if self.query.is_empty() || self.query.mask & text.mask == 0 {
+            return None;
+        }
+
+        let query = &self.query.units;
+        let (m, n) = (query.len(), text.units.len());
+        for table in [&mut self.matched, &mut self.last, &mut self.gap] {
+            table.clear();
+            table.resize(m * n, NONE);
+        }
+
+        for (i, query_unit) in query.iter().enumerate() {
+            let row = i * n;
+            let previous_row = row.wrapping_sub(n);
+            for (j, text_unit) in text.units.iter().enumerate() {
+                let bonus = text.bonuses[j];
+
+                let mut matched = NONE;
+                if query_unit == text_unit && i == 0 {
+                    matched = SCORE_MATCH + bonus * BONUS_FIRST_CHAR_MULTIPLIER
+                        - PENALTY_LATE_MATCH * text.graphemes[j] as i32;
+                } else if query_unit == text_unit && j > 0 {
+                    let consecutive = self.last[previous_row + j - 1]
+                        + SCORE_MATCH
+                        + max(bonus, BONUS_CONSECUTIVE);
+                    let after_gap = self.gap[previous_row + j - 1] + SCORE_MATCH + bonus;
+                    matched = max(consecutive, after_gap);
+                }
+                self.matched[row + j] = matched;
+
+                self.last[row + j] = if i > 0 {
+                    max(matched, self.last[previous_row + j] - PENALTY_SKIP)
+                } else {
+                    matched
+                };
+
+                if j > 0 {
+                    self.gap[row + j] = max(
+                        self.last[row + j - 1] - PENALTY_GAP_START,
+                        self.gap[row + j - 1] - PENALTY_GAP_EXTENSION,
+                    );
+                }
+            }
+        }
+
+        let last_row = &self.last[(m - 1) * n..];
+        // the earliest of the best ends
+        let (end, score) =
+            last_row
+                .iter()
+                .copied()
+                .enumerate()
+                .fold(
+                    (0, NONE),
+                    |best, (j, score)| {
+                        if score > best.1 {
+                            (j, score)
+                        } else {
+                            best
+                        }
+                    },
+                );
+        (score > NONE / 2).then_some((score, end))
+    }
+
+    /// The matched text units of the alignment ending at the text unit, after `align`
+    fn trace_back(&self, text: &Folded, end: usize) -> Vec<usize> {
+        let n = text.units.len();
+        let mut positions = vec![];
+        let (mut i, mut j) = (self.query.units.len() - 1, end);
+        let mut in_gap = false;
+
+        loop {
+            let cell = i * n + j;
+            if in_gap {
+                // the gap is opened after a match, or it's extended
+                in_gap = self.gap[cell] != self.last[cell - 1] - PENALTY_GAP_START;
+                j -= 1;
+                continue;
+            }
+
+            if self.last[cell] != self.matched[cell] {
+                // the query unit is skipped
+                i -= 1;
+                continue;
+            }
+            positions.push(j);
+            // the alignment starts with the first query unit
+            if i == 0 {
+                break;
+            }
+            let consecutive =
+                self.last[cell - n - 1] + SCORE_MATCH + max(text.bonuses[j], BONUS_CONSECUTIVE);
+            in_gap = self.matched[cell] != consecutive;
+            (i, j) = (i - 1, j - 1);
+        }
+
+        positions.reverse();
+        positions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct FuzzyInfo {
+        fitness: i32,
+        positions: Vec<usize>,
+    }
+
+    fn get_fuzzy_info(query: &str, name: &str) -> FuzzyInfo {
+        get_fuzzy_info_with(query, name, true)
+    }
+
+    fn get_fuzzy_info_with(query: &str, name: &str, strip_accents: bool) -> FuzzyInfo {
+        let mut matcher = Matcher::new(query, strip_accents);
+        let name = Folded::new(name, strip_accents);
+        FuzzyInfo {
+            fitness: matcher.get_fitness(&name).unwrap_or_default(),
+            positions: matcher.get_positions(&name).unwrap_or_default(),
+        }
+    }
+
+    #[test]
+    fn test_get_fuzzy_info() {
+        let name = "chromium".to_string();
+        let query = "chomium".to_string();
+        let info = get_fuzzy_info(&query, &name);
+
+        assert_eq!(info.positions, vec![0, 1, 3, 4, 5, 6, 7]);
+        assert!(info.fitness > 0);
+    }
+
+    #[test]
+    fn test_fitness1() {
+        let info1 = get_fuzzy_info("clo", "chromium");
+        let info2 = get_fuzzy_info("clo", "chrootas");
+        let info3 = get_fuzzy_info("clo", "chromapr");
+        println!("{info1:?}");
+        println!("{info2:?}");
+        println!("{info3:?}");
+
+        assert_eq!(info1.positions, vec![0, 3]);
+        assert_eq!(info1.fitness, info2.fitness);
+        assert_eq!(info1.fitness, info3.fitness);
+        assert_eq!(info2.fitness, info3.fitness);
+    }
+
+    #[test]
+    fn test_fitness2() {
+        let info1 = get_fuzzy_info("slack", "badlocks");
+        let info2 = get_fuzzy_info("slack", "com.slack.Slack");
+
+        assert!(info1.fitness < info2.fitness);
+    }
+
+    #[test]
+    fn test_fitness3() {
+        let info1 = get_fuzzy_info("chromim", "commmium Web Browser");
+        let info2 = get_fuzzy_info("chromim", "Chromium Web Browser");
+        println!("{}", info1.fitness);
+        println!("{}", info2.fitness);
+
+        assert!(info1.fitness < info2.fitness);
+    }
+
+    #[test]
+    fn test_shorter_first() {
+        let info1 = get_fuzzy_info("files", "filess");
+        let info2 = get_fuzzy_info("files", "files");
+        println!("{}", info1.fitness);
+        println!("{}", info2.fitness);
+
+        assert!(info1.fitness < info2.fitness);
+    }
+
+    #[test]
+    fn test_fitness4() {
+        let info1 = get_fuzzy_info("files", "something files");
+        let info2 = get_fuzzy_info("files", "files something");
+        println!("{}", info1.fitness);
+        println!("{}", info2.fitness);
+
+        assert!(info1.fitness < info2.fitness);
+    }
+
+    #[test]
+    fn test_fitness5() {
+        let info1 = get_fuzzy_info("clomium", "alikialiki");
+        let info2 = get_fuzzy_info("clomium", "Chromium/usr/bin/chromium %UWeb Browser");
+        println!("{}", info1.fitness);
+        println!("{}", info2.fitness);
+
+        assert!(info1.fitness < info2.fitness);
+    }
+
+    #[test]
+    fn test_fitness6() {
+        let info1 = get_fuzzy_info("clomiumbrowser", "DevhelpdevhelpAPI Documentation Browser");
+        let info2 = get_fuzzy_info("clomiumbrowser", "Chromium/usr/bin/chromium %UWeb Browser");
+        println!("{}", info1.fitness);
+        println!("{}", info2.fitness);
+        assert!(info1.fitness < info2.fitness);
+    }
+
+    #[test]
+    fn test_fitness7() {
+        let info1 = get_fuzzy_info("clomium", "commcomm");
+        let info2 = get_fuzzy_info("clomium", "Chromium/usr/bin/chromium %UWeb Browser");
+        println!("{}", info1.fitness);
+        println!("{}", info2.fitness);
+        assert!(info1.fitness < info2.fitness);
+    }
+
+    #[test]
+    fn test_fitness8() {
+        let info1 = get_fuzzy_info("clomium", "gcloud-crc32cgcloud-crc32c");
+        let info2 = get_fuzzy_info("clomium", "Chromium/usr/bin/chromium %UWeb Browser");
+        println!("{}", info1.fitness);
+        println!("{}", info2.fitness);
+        assert!(info1.fitness < info2.fitness);
+    }
+
+    #[test]
+    fn test_distance() {
+        let info1 = get_fuzzy_info("ac", "abcdefghijkl");
+        let info2 = get_fuzzy_info("ad", "abcdefghijkl");
+        let info3 = get_fuzzy_info("ae", "abcdefghijkl");
+        println!("{}", info1.fitness);
+        println!("{}", info2.fitness);
+        println!("{}", info3.fitness);
+        assert!(info1.fitness > info2.fitness);
+        assert!(info2.fitness > info3.fitness);
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        // the start of the words wins over the same letters inside a word
+        let info1 = get_fuzzy_info("gc", "magic");
+        let info2 = get_fuzzy_info("gc", "Google Chrome");
+        assert!(info1.fitness < info2.fitness);
+        assert_eq!(info2.positions, vec![0, 7]);
+
+        let info1 = get_fuzzy_info("code", "visualstudiocode");
+        let info2 = get_fuzzy_info("code", "visualStudioCode");
+        assert!(info1.fitness < info2.fitness);
+
+        // a consecutive run wins over scattered letters
+        let info1 = get_fuzzy_info("term", "xtxexrxm");
+        let info2 = get_fuzzy_info("term", "xterm");
+        assert!(info1.fitness < info2.fitness);
+        assert_eq!(info2.positions, vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_no_match() {
+        assert!(Matcher::new("xyz", true)
+            .get_fitness(&Folded::new("chromium", true))
+            .is_none());
+        assert!(Matcher::new("", true)
+            .get_fitness(&Folded::new("chromium", true))
+            .is_none());
+        assert!(Matcher::new("a", true)
+            .get_fitness(&Folded::new("", true))
+            .is_none());
+    }
+
+    #[test]
+    fn test_unicode_case() {
+        let info = get_fuzzy_info_with("ÉDITEUR", "éditeur", false);
+        assert_eq!(info.positions, vec![0, 1, 2, 3, 4, 5, 6]);
+        // both letters of ss are matched by ß
+        assert_eq!(
+            get_fuzzy_info_with("strasse", "Straße", false).positions,
+            vec![0, 1, 2, 3, 4, 5]
+        );
+        assert_eq!(
+            get_fuzzy_info_with("σοφος", "ΣΟΦΟΣ", false).positions,
+            vec![0, 1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_accents() {
+        assert_eq!(get_fuzzy_info("uber", "Über").positions, vec![0, 1, 2, 3]);
+        assert_eq!(get_fuzzy_info("Über", "uber").positions, vec![0, 1, 2, 3]);
+        // the first letter has to match
+        assert!(get_fuzzy_info_with("uber", "Über", false)
+            .positions
+            .is_empty());
+        assert_eq!(
+            get_fuzzy_info_with("bür", "Über", false).positions,
+            vec![1, 3]
+        );
+
+        let info1 = get_fuzzy_info("e", "Éditeur");
+        let info2 = get_fuzzy_info("e", "Viewer");
+        assert!(info1.fitness > info2.fitness);
+    }
+
+    #[test]
+    fn test_non_latin_names() {
+        let info1 = get_fuzzy_info("текст", "Текстовый редактор");
+        let info2 = get_fuzzy_info("текст", "Терминал");
+        assert_eq!(info1.positions, vec![0, 1, 2, 3, 4]);
+        assert!(info1.fitness > info2.fitness);
+
+        let info = get_fuzzy_info("Επεξεργαστής", "επεξεργαστης κειμένου");
+        assert_eq!(info.positions, (0..12).collect::<Vec<_>>());
+
+        let info1 = get_fuzzy_info("编辑", "文本编辑器");
+        let info2 = get_fuzzy_info("编辑", "编程工具");
+        assert_eq!(info1.positions, vec![2, 3]);
+        assert!(info1.fitness > info2.fitness);
+    }
+
+    #[test]
+    fn test_graphemes() {
+        // a family is one grapheme, it's not matched by its members
+        let family = "👨\u{200D}👩\u{200D}👧";
+        assert!(get_fuzzy_info("👨", &format!("{family} Chat"))
+            .positions
+            .is_empty());
+        assert_eq!(
+            get_fuzzy_info(family, &format!("{family} Chat")).positions,
+            vec![0]
+        );
+        // a combining accent is a part of its letter
+        assert_eq!(
+            get_fuzzy_info_with("cafe", "cafe\u{301}", false).positions,
+            vec![0, 1, 2]
+        );
+
+        // a long name is cut without splitting a grapheme
+        let name = "ő".repeat(MAX_NAME_LENGTH * 2);
+        assert_eq!(get_fuzzy_info_with("ő", &name, false).positions, vec![0]);
+    }
+
+    #[test]
+    fn test_typos() {
+        let fitness = |query: &str, name: &str, tolerates_typos: bool| {
+            let mut matcher = Matcher::new(query, true);
+            matcher.set_typos(tolerates_typos);
+            matcher.get_fitness(&Folded::new(name, true))
+        };
+
+        // swapped letters rank the intended app first
+        assert!(
+            fitness("spotfiy", "Spotify", false)
+                < fitness("spotfiy", "Sports Tracker Fitness", false)
+        );
+        assert!(
+            fitness("spotfiy", "Spotify", true)
+                > fitness("spotfiy", "Sports Tracker Fitness", true)
+        );
+        assert!(fitness("chormium", "Chromium", true) > fitness("chormium", "Chromium", false));
+        assert!(
+            fitness("thnuderbrid", "Thunderbird", true)
+                > fitness("thnuderbrid", "Thunderbird", false)
+        );
+        // at the start of a later word too
+        assert!(
+            fitness("inkspace", "Open Inkscape", true)
+                > fitness("inkspace", "Open Inkscape", false)
+        );
+
+        // a typo is not better than typing it right
+        assert!(fitness("steam", "Steam", true) > fitness("setam", "Steam", true));
+        assert_eq!(
+            fitness("steam", "Steam", true),
+            fitness("steam", "Steam", false)
+        );
+        // too short for a typo, and a typo doesn't make a text match
+        assert_eq!(fitness("gmi", "GIMP", true), fitness("gmi", "GIMP", false));
+        assert_eq!(fitness("xhromium", "Chromium", true), None);
+    }
+}
@@ -0,0 +1,8 @@
+//! Matching the queries with the texts of the items in the lists of the plugins
+
+pub mod best;
+pub mod cache;
+pub mod fuzzy;
+pub mod query;
+pub mod translit;
+pub mod typo;
@@ -0,0 +1,517 @@
+//! The search operators of fzf:
+//! - `fire` matches fuzzily, `'fire` contains it, `^fire` starts with it,
+//!   `fox$` ends with it, and `^firefox$` is exactly it
+//! - `!fire` doesn't contain it, also `!^fire` and `!fox$`
+//! - `cat:game` or `exec:^steam` only matches the given field of the items
+//! - the terms separated by spaces have to match all, the ones joined by `|` any of them
+
+use super::{
+    cache::PrefixCache,
+    fuzzy::{Folded, Matcher},
+};
+
+/// Marks the keys of the queries with operators, it's neither a char nor a hashed grapheme
+const OPERATOR_KEY: u32 = 0x7fff_ffff;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    Fuzzy,
+    Contains,
+    Prefix,
+    Suffix,
+    Equal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    /// only this field of the items is matched
+    pub field: Option<String>,
+    pub pattern: Pattern,
+    pub text: String,
+    pub negated: bool,
+}
+
+impl Term {
+    /// Returns `None` if nothing remains after the operators.
+    /// Only the given fields are recognized, so a URL is not taken for a field.
+    fn parse(token: &str, fields: &[&str]) -> Option<Self> {
+        let (negated, token) = match token.strip_prefix('!') {
+            Some(token) => (true, token),
+            None => (false, token),
+        };
+        let (field, token) = match token.split_once(':') {
+            Some((field, token)) if fields.contains(&field.to_lowercase().as_str()) => {
+                (Some(field.to_lowercase()), token)
+            }
+            _ => (None, token),
+        };
+
+        let (pattern, text) = if let Some(text) = token.strip_prefix('\'') {
+            (Pattern::Contains, text)
+        } else if let Some(text) = token.strip_prefix('^') {
+            match text.strip_suffix('$') {
+                Some(text) => (Pattern::Equal, text),
+                None => (Pattern::Prefix, text),
+            }
+        } else if let Some(text) = token.strip_suffix('$') {
+            (Pattern::Suffix, text)
+        } else if negated {
+            // a negated fuzzy match would exclude almost everything
+            (Pattern::Contains, token)
+        } else {
+            (Pattern::Fuzzy, token)
+        };
+
+        (!text.is_empty()).then(|| Self {
+            field,
+            pattern,
+            text: text.to_string(),
+            negated,
+        })
+    }
+}
+
+/// Every group has to match, by any of its terms
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub groups: Vec<Vec<Term>>,
+}
+
+impl Query {
+    /// The fields are the ones the items can be filtered by, like `cat`
+    pub fn parse(text: &str, fields: &[&str]) -> Self {
+        let mut groups: Vec<Vec<Term>> = vec![];
+        let mut joins_previous = false;
+
+        for token in text.split_whitespace() {
+            let terms: Vec<Term> = token
+                .split('|')
+                .filter_map(|part| Term::parse(part, fields))
+                .collect();
+            joins_previous |= token.starts_with('|');
+
+            if !terms.is_empty() {
+                match groups.last_mut() {
+                    Some(group) if joins_previous => group.extend(terms),
+                    _ => groups.push(terms),
+                }
+                joins_previous = false;
+            }
+            joins_previous |= token.ends_with('|');
+        }
+
+        Self { groups }
+    }
+
+    /// The text of a query without operators
+    pub fn as_fuzzy(&self) -> Option<&str> {
+        match self.groups.as_slice() {
+            [group] => match group.as_slice() {
+                [Term {
+                    field: None,
+                    pattern: Pattern::Fuzzy,
+                    text,
+                    negated: false,
+                }] => Some(text),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Whether the token of a query has any operators, or it's a plain word
+pub fn has_operators(token: &str, fields: &[&str]) -> bool {
+    Query::parse(token, fields).as_fuzzy().is_none()
+}
+
+/// A text of an item, like the name or the categories of an app
+pub struct Field {
+    /// the name the queries can filter by
+    pub name: &'static str,
+    pub text: Folded,
+    /// subtracted from the fitness, so the more important fields win
+    pub penalty: i32,
+}
+
+struct TermMatcher {
+    field: Option<String>,
+    pattern: Pattern,
+    negated: bool,
+    matcher: Matcher,
+}
+
+impl TermMatcher {
+    fn is_matching(&self, field: &Field) -> bool {
+        match &self.field {
+            Some(name) => name == field.name,
+            None => true,
+        }
+    }
+
+    /// The fitness of the text by the pattern, without the negation
+    fn get_fitness(&mut self, text: &Folded) -> Option<i32> {
+        let term = self.matcher.query();
+        let matches = match self.pattern {
+            Pattern::Fuzzy => true,
+            Pattern::Contains => text.contains(term),
+            Pattern::Prefix => text.starts_with(term),
+            Pattern::Suffix => text.ends_with(term),
+            Pattern::Equal => text.is_same_text(term),
+        };
+        if matches {
+            self.matcher.get_fitness(text)
+        } else {
+            None
+        }
+    }
+
+    /// The fitness of the best field, and the index of the field. Negated terms have no field.
+    fn get_best(&mut self, fields: &[Field]) -> Option<(i32, Option<usize>)> {
+        let mut best = None;
+        for (i, field) in fields.iter().enumerate() {
+            if !self.is_matching(field) {
+                continue;
+            }
+            if let Some(fitness) = self.get_fitness(&field.text) {
+                if self.negated {
+                    return None;
+                }
+                let fitness = fitness - field.penalty;
+                // the first field wins a tie
+                if !matches!(best, Some((best, _)) if best >= fitness) {
+                    best = Some((fitness, Some(i)));
+                }
+            }
+        }
+
+        if self.negated {
+            Some((0, None))
+        } else {
+            best
+        }
+    }
+}
+
+/// A query prepared for matching the items
+pub struct QueryMatcher {
+    groups: Vec<Vec<TermMatcher>>,
+    /// identifies the query
+    key: Vec<u32>,
+}
+
+impl QueryMatcher {
+    pub fn new(query: &Query, strip_accents: bool) -> Self {
+        let groups: Vec<Vec<TermMatcher>> = query
+            .groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|term| TermMatcher {
+                        field: term.field.clone(),
+                        pattern: term.pattern,
+                        negated: term.negated,
+                        matcher: Matcher::new(&term.text, strip_accents),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let key = match query.as_fuzzy() {
+            Some(_) => groups[0][0].matcher.units().to_vec(),
+            None => {
+                let mut key = vec![OPERATOR_KEY];
+                key.extend(format!("{query:?}").chars().map(u32::from));
+                key.push(OPERATOR_KEY);
+                key
+            }
+        };
+        Self { groups, key }
+    }
+
+    /// The fuzzy terms tolerate typos, see `Matcher::set_typos`
+    pub fn with_typos(mut self, tolerates_typos: bool) -> Self {
+        for term in self.groups.iter_mut().flatten() {
+            if term.pattern == Pattern::Fuzzy && !term.negated {
+                term.matcher.set_typos(tolerates_typos);
+            }
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Identifies the query in a `PrefixCache`.
+    /// A query without operators is keyed by its folded text, so the key of a longer query starts with it,
+    /// and the items matching the longer one are among the items matching the shorter one.
+    /// A query with operators only matches its own key.
+    pub fn key(&self) -> &[u32] {
+        &self.key
+    }
+
+    /// The sum of the fitness of the groups, or `None` if a group doesn't match
+    pub fn get_fitness(&mut self, fields: &[Field]) -> Option<i32> {
+        let mut sum = 0;
+        for group in &mut self.groups {
+            sum += Self::get_best_of_group(group, fields)?.0;
+        }
+        Some(sum)
+    }
+
+    /// The matched graphemes of the field, by the terms which match that field the best
+    pub fn get_positions(&mut self, fields: &[Field], field: usize) -> Vec<usize> {
+        let mut positions = vec![];
+        for group in &mut self.groups {
+            if let Some((_, Some((term, best_field)))) = Self::get_best_of_group(group, fields) {
+                if best_field == field {
+                    let matcher = &mut group[term].matcher;
+                    positions.extend(
+                        matcher
+                            .get_positions(&fields[field].text)
+                            .unwrap_or_default(),
+                    );
+                }
+            }
+        }
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+
+    /// The fitness of the best matching term of the group, with the term and its best field
+    fn get_best_of_group(
+        group: &mut [TermMatcher],
+        fields: &[Field],
+    ) -> Option<(i32, Option<(usize, usize)>)> {
+        let mut best: Option<(i32, Option<(usize, usize)>)> = None;
+        for (i, term) in group.iter_mut().enumerate() {
+            if let Some((fitness, field)) = term.get_best(fields) {
+                let current = (fitness, field.map(|field| (i, field)));
+                best = Some(match best {
+                    Some(best) if best.0 >= fitness => best,
+                    _ => current,
+                });
+            }
+        }
+        best
+    }
+}
+
+/// Filters a list by the queries typed one after the other. Each item is matched by a single text.
+/// A query typed further only checks the items matching the recent query it extends.
+pub struct Filter<T> {
+    items: Vec<T>,
+    strip_accents: bool,
+    /// the matching items of the recent queries, the best first
+    matches: PrefixCache<u32, Vec<usize>>,
+}
+
+/// The accents are ignored, like by the launcher by default
+impl<T> Default for Filter<T> {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+impl<T> Filter<T> {
+    pub fn new(strip_accents: bool) -> Self {
+        Self {
+            items: Vec::new(),
+            strip_accents,
+            matches: PrefixCache::default(),
+        }
+    }
+}
+
+impl<T: AsRef<str> + PartialEq> Filter<T> {
+    /// The matches of the earlier queries are forgotten if the items changed
+    pub fn set_items(&mut self, items: Vec<T>) {
+        if self.items != items {
+            self.items = items;
+            self.matches = PrefixCache::default();
+        }
+    }
+
+    /// The items matching the query, the best first
+    pub fn filter(&mut self, query: &str) -> Vec<&T> {
+        let mut matcher = QueryMatcher::new(&Query::parse(query, &[]), self.strip_accents);
+        if matcher.is_empty() {
+            return self.items.iter().collect();
+        }
+
+        let key = matcher.key().to_vec();
+        if self.matches.get(&key).is_none() {
+            let mut candidates = match self.matches.get_longest_prefix(&key) {
+                Some(matches) => matches.clone(),
+                None => (0..self.items.len()).collect(),
+            };
+            // in the order of the items, so the equally good ones keep their order
+            candidates.sort_unstable();
+            let matches = self.search(&mut matcher, candidates);
+            self.matches.insert(key.clone(), matches);
+        }
+        self.matches
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .map(|i| &self.items[*i])
+            .collect()
+    }
+
+    fn search(&self, matcher: &mut QueryMatcher, candidates: Vec<usize>) -> Vec<usize> {
+        let mut matches: Vec<(i32, usize)> = candidates
+            .into_iter()
+            .filter_map(|i| {
+                let fields = [Field {
+                    name: "text",
+                    text: Folded::new(self.items[i].as_ref(), self.strip_accents),
+                    penalty: 0,
+                }];
+                Some((matcher.get_fitness(&fields)?, i))
+            })
+            .collect();
+        // a stable sort, the equally good items keep their order
+        matches.sort_by_key(|(fitness, _)| std::cmp::Reverse(*fitness));
+        matches.into_iter().map(|(_, i)| i).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: [&str; 2] = ["exec", "cat"];
+
+    fn term(pattern: Pattern, text: &str) -> Term {
+        Term {
+            field: None,
+            pattern,
+            text: text.to_string(),
+            negated: false,
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let query = Query::parse("fire 'fox ^web bar$ ^x$", &FIELDS);
+        assert_eq!(
+            query.groups,
+            vec![
+                vec![term(Pattern::Fuzzy, "fire")],
+                vec![term(Pattern::Contains, "fox")],
+                vec![term(Pattern::Prefix, "web")],
+                vec![term(Pattern::Suffix, "bar")],
+                vec![term(Pattern::Equal, "x")],
+            ]
+        );
+
+        let query = Query::parse("!Cat:game exec:^steam https://github.com", &FIELDS);
+        assert_eq!(
+            query.groups,
+            vec![
+                vec![Term {
+                    field: Some("cat".to_string()),
+                    negated: true,
+                    ..term(Pattern::Contains, "game")
+                }],
+                vec![Term {
+                    field: Some("exec".to_string()),
+                    ..term(Pattern::Prefix, "steam")
+                }],
+                vec![term(Pattern::Fuzzy, "https://github.com")],
+            ]
+        );
+
+        // the ways of OR
+        let expected = vec![vec![term(Pattern::Fuzzy, "a"), term(Pattern::Fuzzy, "b")]];
+        for text in ["a | b", "a|b", "a |b", "a| b", "a | | b"] {
+            assert_eq!(Query::parse(text, &FIELDS).groups, expected, "{text}");
+        }
+
+        assert!(Query::parse("! ^ ' |", &FIELDS).groups.is_empty());
+        assert_eq!(Query::parse(" fire ", &FIELDS).as_fuzzy(), Some("fire"));
+        assert!(has_operators("cat:game", &FIELDS));
+        assert!(!has_operators("firefox", &FIELDS));
+    }
+
+    #[test]
+    fn test_filter() {
+        let items = vec![
+            "email/work",
+            "email/personal",
+            "bank/main",
+            "social/mastodon",
+            "work/vpn",
+        ];
+        let mut filter = Filter::new(true);
+        filter.set_items(items);
+        let mut filter = |query: &str| {
+            filter
+                .filter(query)
+                .into_iter()
+                .copied()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(filter("email"), vec!["email/work", "email/personal"]);
+        assert_eq!(filter("'work"), vec!["work/vpn", "email/work"]);
+        assert_eq!(filter("^work"), vec!["work/vpn"]);
+        assert_eq!(filter("work$"), vec!["email/work"]);
+        assert_eq!(filter("^bank/main$"), vec!["bank/main"]);
+        assert_eq!(filter("email !work"), vec!["email/personal"]);
+        // more matching letters are better
+        assert_eq!(
+            filter("^bank | ^social"),
+            vec!["social/mastodon", "bank/main"]
+        );
+        assert_eq!(filter("").len(), 5);
+
+        // the queries typed further start from the earlier matches, and they find the same
+        assert_eq!(filter("e"), filter("e"));
+        assert_eq!(filter("em"), vec!["email/work", "email/personal"]);
+        assert_eq!(filter("ema !p"), vec!["email/work"]);
+        assert_eq!(filter("em"), vec!["email/work", "email/personal"]);
+    }
+
+    #[test]
+    fn test_fields() {
+        let fields = |name: &str, exec: &str| {
+            [
+                Field {
+                    name: "name",
+                    text: Folded::new(name, true),
+                    penalty: 0,
+                },
+                Field {
+                    name: "exec",
+                    text: Folded::new(exec, true),
+                    penalty: 10,
+                },
+            ]
+        };
+        let mut matcher = QueryMatcher::new(&Query::parse("exec:^fire", &["exec"]), true);
+        assert!(matcher.get_fitness(&fields("Web", "firefox")).is_some());
+        assert!(matcher.get_fitness(&fields("Firefox", "web")).is_none());
+
+        let mut matcher = QueryMatcher::new(&Query::parse("web fox", &["exec"]), true);
+        let firefox = fields("Firefox Web", "firefox");
+        assert!(matcher.get_fitness(&firefox).is_some());
+        assert_eq!(matcher.get_positions(&firefox, 0), vec![0, 5, 6, 8, 9, 10]);
+    }
+
+    #[test]
+    fn test_key() {
+        let key = |text: &str| {
+            QueryMatcher::new(&Query::parse(text, &[]), true)
+                .key()
+                .to_vec()
+        };
+        assert!(key("firefox").starts_with(&key("fire")));
+        assert!(!key("fire$").starts_with(&key("fire")));
+        assert!(!key("!fires").starts_with(&key("!fire")));
+        assert_eq!(key("a | b"), key("a|b"));
+    }
+}
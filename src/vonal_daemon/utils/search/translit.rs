@@ -0,0 +1,46 @@
+//! Latin spellings of the texts in other scripts, so they can be typed on a Latin keyboard layout:
+//! Cyrillic and Greek letter by letter, Han characters in pinyin and kana in romaji.
+
+use deunicode::deunicode;
+
+/// Whether the text has letters which can't be typed on a Latin layout.
+/// The accented Latin letters are matched by folding the accents instead.
+fn has_non_latin_letters(text: &str) -> bool {
+    text.chars()
+        .any(|c| c.is_alphabetic() && !matches!(c, '\u{0}'..='\u{24f}' | '\u{1e00}'..='\u{1eff}'))
+}
+
+/// The Latin spelling of the text, or `None` if it's Latin already
+pub fn transliterate(text: &str) -> Option<String> {
+    if !has_non_latin_letters(text) {
+        return None;
+    }
+    // the syllables of Han characters are separated by spaces, which are kept as word boundaries
+    let latin = deunicode(text)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!latin.is_empty() && latin != text).then_some(latin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transliterate() {
+        assert_eq!(transliterate("Терминал").as_deref(), Some("Terminal"));
+        assert_eq!(transliterate("Τερματικό").as_deref(), Some("Termatiko"));
+        assert_eq!(transliterate("终端").as_deref(), Some("Zhong Duan"));
+        assert_eq!(transliterate("テキスト").as_deref(), Some("tekisuto"));
+        // mixed with Latin
+        assert_eq!(
+            transliterate("GNOME Терминал").as_deref(),
+            Some("GNOME Terminal")
+        );
+
+        assert_eq!(transliterate("Terminal"), None);
+        assert_eq!(transliterate("Éditeur de texte"), None);
+        assert_eq!(transliterate("2048"), None);
+    }
+}
@@ -0,0 +1,95 @@
+//! The edit distance of a query from the start of a word, for the queries with typos.
+//! It's the optimal string alignment distance: a letter inserted, deleted, replaced,
+//! or two adjacent letters swapped, like `chormium` for `chromium`, count as one edit.
+
+/// The most edits allowed in a query, the short queries would match almost anything with a typo
+pub fn get_max_distance(query_length: usize) -> usize {
+    match query_length {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+/// The least edits turning the query into a prefix of the text, if it's at most the given distance.
+/// The rows are kept between the calls.
+pub fn get_prefix_distance(
+    query: &[u32],
+    text: &[u32],
+    max_distance: usize,
+    rows: &mut [Vec<usize>; 3],
+) -> Option<usize> {
+    // the prefix can't be longer than the query with the insertions
+    let text = &text[..text.len().min(query.len() + max_distance)];
+    let n = text.len();
+    let [before_previous, previous, current] = rows;
+    for row in [&mut *before_previous, &mut *previous, &mut *current] {
+        row.clear();
+        row.resize(n + 1, 0);
+    }
+    // the distances of the empty query from the prefixes, all of them are insertions
+    for (j, distance) in previous.iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=query.len() {
+        current[0] = i;
+        for j in 1..=n {
+            let cost = usize::from(query[i - 1] != text[j - 1]);
+            let mut distance = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && query[i - 1] == text[j - 2] && query[i - 2] == text[j - 1] {
+                distance = distance.min(before_previous[j - 2] + 1);
+            }
+            current[j] = distance;
+        }
+        // no prefix can get closer than the closest one so far
+        if current.iter().min().copied().unwrap_or_default() > max_distance {
+            return None;
+        }
+        std::mem::swap(before_previous, previous);
+        std::mem::swap(previous, current);
+    }
+
+    // the text after the prefix doesn't count
+    previous
+        .iter()
+        .copied()
+        .min()
+        .filter(|distance| *distance <= max_distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(query: &str, text: &str) -> Option<usize> {
+        let units = |text: &str| text.chars().map(u32::from).collect::<Vec<_>>();
+        let query = units(query);
+        get_prefix_distance(
+            &query,
+            &units(text),
+            get_max_distance(query.len()),
+            &mut Default::default(),
+        )
+    }
+
+    #[test]
+    fn test_prefix_distance() {
+        assert_eq!(distance("chromium", "chromium"), Some(0));
+        assert_eq!(distance("chrom", "chromium"), Some(0));
+        // swapped
+        assert_eq!(distance("chormium", "chromium"), Some(1));
+        // missing, extra and wrong letters
+        assert_eq!(distance("firfox", "firefox"), Some(1));
+        assert_eq!(distance("fireefox", "firefox"), Some(1));
+        assert_eq!(distance("firwfox", "firefox"), Some(1));
+        assert_eq!(distance("thnuderbrid", "thunderbird"), Some(2));
+
+        assert_eq!(distance("fxfire", "firefox"), None);
+        // too short for a typo
+        assert_eq!(distance("fxo", "fox"), None);
+        assert_eq!(distance("fox", "fox"), Some(0));
+    }
+}
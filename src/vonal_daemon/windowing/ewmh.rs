@@ -0,0 +1,179 @@
+use std::ffi::{c_long, c_uchar, c_ulong, c_void, CStr};
+
+use x11::xlib::{_XDisplay, Atom, Window};
+
+/// A connection to the X server for the requests to the window manager, by EWMH.
+/// It's either borrowed from the window, or opened for a thread, because an Xlib connection is not shared by threads.
+pub struct Connection {
+    display: *mut _XDisplay,
+    /// the opened connections are closed when they are dropped
+    is_opened: bool,
+}
+
+// the opened connection is used only by the thread which it's moved to
+unsafe impl Send for Connection {}
+
+impl Connection {
+    /// Opens a new connection to the display of `$DISPLAY`
+    pub fn open() -> Option<Self> {
+        let display = unsafe { x11::xlib::XOpenDisplay(std::ptr::null()) };
+        (!display.is_null()).then_some(Self {
+            display,
+            is_opened: true,
+        })
+    }
+
+    /// # Safety
+    /// The display has to stay open while the connection is used.
+    pub unsafe fn borrow(display: *mut _XDisplay) -> Self {
+        Self {
+            display,
+            is_opened: false,
+        }
+    }
+
+    /// The number of the workspaces and their names, as the window manager tells them.
+    /// The names can be fewer than the workspaces.
+    pub fn get_desktops(&self) -> (usize, Vec<String>) {
+        let root = self.get_root_window();
+        let count: Vec<c_ulong> =
+            self.get_window_property(root, c"_NET_NUMBER_OF_DESKTOPS", x11::xlib::XA_CARDINAL);
+        let utf8_string = self.get_atom(c"UTF8_STRING");
+        let names: Vec<u8> = self.get_window_property(root, c"_NET_DESKTOP_NAMES", utf8_string);
+
+        let names = names
+            .split(|byte| *byte == 0)
+            .map(|name| String::from_utf8_lossy(name).into_owned())
+            .collect::<Vec<_>>();
+        let count = count.first().map_or(0, |count| *count as usize);
+        (count, names)
+    }
+
+    /// The managed windows of a launch, by their `_NET_WM_PID`, or by their `_NET_STARTUP_ID`,
+    /// because the process of the window differs if it was started by a wrapper, like firejail or flatpak
+    pub fn find_launched_windows(&self, pid: u32, startup_id: Option<&str>) -> Vec<Window> {
+        let windows: Vec<c_ulong> = self.get_window_property(
+            self.get_root_window(),
+            c"_NET_CLIENT_LIST",
+            x11::xlib::XA_WINDOW,
+        );
+        let utf8_string = self.get_atom(c"UTF8_STRING");
+        windows
+            .into_iter()
+            .filter(|window| {
+                let window_pid: Vec<c_ulong> =
+                    self.get_window_property(*window, c"_NET_WM_PID", x11::xlib::XA_CARDINAL);
+                if window_pid.first() == Some(&(pid as c_ulong)) {
+                    return true;
+                }
+                let Some(startup_id) = startup_id else {
+                    return false;
+                };
+                let window_startup_id: Vec<u8> =
+                    self.get_window_property(*window, c"_NET_STARTUP_ID", utf8_string);
+                window_startup_id
+                    .strip_suffix(&[0])
+                    .unwrap_or(&window_startup_id)
+                    == startup_id.as_bytes()
+            })
+            .collect()
+    }
+
+    /// Asks the window manager to move the window to the workspace
+    pub fn move_window_to_desktop(&self, window: Window, desktop: usize) {
+        // the source is a pager, which acts for the user
+        self.send_root_message(window, c"_NET_WM_DESKTOP", [desktop as c_long, 2, 0, 0, 0]);
+    }
+
+    /// Asks the window manager to move the window to the position, e.g. to another monitor
+    pub fn move_window_to_position(&self, window: Window, x: i32, y: i32) {
+        // the x and y are set, the gravity of the window is kept, and the source is a pager
+        let flags = (1 << 8) | (1 << 9) | (2 << 12);
+        let data = [flags, x as c_long, y as c_long, 0, 0];
+        self.send_root_message(window, c"_NET_MOVERESIZE_WINDOW", data);
+    }
+
+    fn get_root_window(&self) -> Window {
+        unsafe { x11::xlib::XDefaultRootWindow(self.display) }
+    }
+
+    fn get_atom(&self, name: &CStr) -> Atom {
+        unsafe { x11::xlib::XInternAtom(self.display, name.as_ptr(), x11::xlib::False) }
+    }
+
+    /// The items of the property, or nothing if it's missing or its type differs.
+    /// Xlib returns the 32 bit items as `c_ulong`s and the 8 bit ones as bytes.
+    fn get_window_property<T: Copy>(&self, window: Window, name: &CStr, kind: Atom) -> Vec<T> {
+        let property = self.get_atom(name);
+
+        let mut actual_kind: Atom = 0;
+        let mut format: i32 = 0;
+        let mut length: c_ulong = 0;
+        let mut bytes_after: c_ulong = 0;
+        let mut data: *mut c_uchar = std::ptr::null_mut();
+        unsafe {
+            let status = x11::xlib::XGetWindowProperty(
+                self.display,
+                window,
+                property,
+                0,
+                c_long::MAX,
+                x11::xlib::False,
+                kind,
+                &mut actual_kind,
+                &mut format,
+                &mut length,
+                &mut bytes_after,
+                &mut data,
+            );
+            if data.is_null() {
+                return vec![];
+            }
+            let expected_format = if std::mem::size_of::<T>() == 1 { 8 } else { 32 };
+            let items = match status == x11::xlib::Success as i32
+                && actual_kind == kind
+                && format == expected_format
+            {
+                true => std::slice::from_raw_parts(data as *const T, length as usize).to_vec(),
+                false => vec![],
+            };
+            x11::xlib::XFree(data as *mut c_void);
+            items
+        }
+    }
+
+    /// Sends a client message about the window to the root window, where the window manager handles it
+    fn send_root_message(&self, window: Window, name: &CStr, data: [c_long; 5]) {
+        let message_type = self.get_atom(name);
+
+        unsafe {
+            let mut client_message: x11::xlib::XClientMessageEvent = std::mem::zeroed();
+            client_message.type_ = x11::xlib::ClientMessage;
+            client_message.display = self.display;
+            client_message.window = window;
+            client_message.message_type = message_type;
+            client_message.format = 32;
+            for (i, value) in data.into_iter().enumerate() {
+                client_message.data.set_long(i, value);
+            }
+
+            let mut event = x11::xlib::XEvent { client_message };
+            x11::xlib::XSendEvent(
+                self.display,
+                self.get_root_window(),
+                x11::xlib::False,
+                x11::xlib::SubstructureRedirectMask | x11::xlib::SubstructureNotifyMask,
+                &mut event,
+            );
+            x11::xlib::XFlush(self.display);
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if self.is_opened {
+            unsafe { x11::xlib::XCloseDisplay(self.display) };
+        }
+    }
+}
@@ -8,11 +8,14 @@ use glutin_winit::GlWindow;
 use raw_window_handle::{
     HasRawDisplayHandle, HasRawWindowHandle, RawDisplayHandle, RawWindowHandle,
 };
+use std::ffi::{c_char, c_void};
 use winit::dpi::PhysicalSize;
 use winit::event_loop::EventLoop;
 use winit::window::WindowBuilder;
 use x11::xlib::_XDisplay;
 
+pub mod ewmh;
+
 pub fn create_display<TUserEvent>(
     event_loop: &EventLoop<TUserEvent>,
 ) -> (GlutinWindowContext, egui_glow::painter::Context) {
@@ -169,7 +172,62 @@ impl GlutinWindowContext {
         monitors.next()
     }
 
-    fn query_pointer(&self) -> (i32, i32) {
+    /// Sends a startup notification message to the root window, in 20 byte long client messages.
+    pub fn send_startup_info(&self, message: &str) {
+        let (connection, window) = self.get_xlib_handles();
+        let connection = connection as *mut _XDisplay;
+
+        let mut bytes = message.as_bytes().to_vec();
+        bytes.push(0);
+
+        unsafe {
+            let begin_atom = x11::xlib::XInternAtom(
+                connection,
+                c"_NET_STARTUP_INFO_BEGIN".as_ptr(),
+                x11::xlib::False,
+            );
+            let info_atom =
+                x11::xlib::XInternAtom(connection, c"_NET_STARTUP_INFO".as_ptr(), x11::xlib::False);
+            let root = x11::xlib::XDefaultRootWindow(connection);
+
+            for (i, chunk) in bytes.chunks(20).enumerate() {
+                let mut client_message: x11::xlib::XClientMessageEvent = std::mem::zeroed();
+                client_message.type_ = x11::xlib::ClientMessage;
+                client_message.display = connection;
+                client_message.window = window;
+                client_message.message_type = if i == 0 { begin_atom } else { info_atom };
+                client_message.format = 8;
+                for (j, byte) in chunk.iter().enumerate() {
+                    client_message.data.set_byte(j, *byte as c_char);
+                }
+
+                let mut event = x11::xlib::XEvent { client_message };
+                x11::xlib::XSendEvent(
+                    connection,
+                    root,
+                    x11::xlib::False,
+                    x11::xlib::PropertyChangeMask,
+                    &mut event,
+                );
+            }
+            x11::xlib::XFlush(connection);
+        }
+    }
+
+    /// The number of the X screen, which startup notification messages refer to
+    pub fn get_screen_number(&self) -> i32 {
+        let (connection, _) = self.get_xlib_handles();
+        unsafe { x11::xlib::XDefaultScreen(connection as *mut _XDisplay) }
+    }
+
+    /// The number of the workspaces and their names, see [`ewmh::Connection::get_desktops`]
+    pub fn get_desktops(&self) -> (usize, Vec<String>) {
+        let (connection, _) = self.get_xlib_handles();
+        // the connection of the window is open as long as the window
+        unsafe { ewmh::Connection::borrow(connection as *mut _XDisplay) }.get_desktops()
+    }
+
+    fn get_xlib_handles(&self) -> (*mut c_void, x11::xlib::Window) {
         let raw_window_handle = self.window().raw_window_handle();
         let raw_display_handle = self.window().raw_display_handle();
         let connection = if let RawDisplayHandle::Xlib(raw) = raw_display_handle {
@@ -182,6 +240,11 @@ impl GlutinWindowContext {
         } else {
             panic!("(Window) We only support X.org over Xlib")
         };
+        (connection, window)
+    }
+
+    fn query_pointer(&self) -> (i32, i32) {
+        let (connection, window) = self.get_xlib_handles();
         let mut r: x11::xlib::Window = Default::default();
         let mut c: x11::xlib::Window = Default::default();
         let mut x: i32 = Default::default();